@@ -31,6 +31,16 @@ fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
+    c.bench_function("insert_random_char_fragmented", |b| {
+        let piece_table = &mut PieceTable::new(TEXT.to_string());
+        for _ in 0..10000 {
+            piece_table.insert("a", random::<usize>() % piece_table.length);
+        }
+        b.iter(|| {
+            piece_table.insert("a", random::<usize>() % piece_table.length);
+        });
+    });
+
     c.bench_function("insert_start_char", |b| {
         let piece_table = &mut PieceTable::new(TEXT.to_string());
         b.iter(|| {
@@ -33,6 +33,16 @@ fn criterion_benchmark(c: &mut Criterion) {
             piece_table.line_at(random::<usize>() % line_count);
         });
     });
+    c.bench_function("line_content_random_fragmented", |b| {
+        let piece_table = &mut PieceTable::new(TEXT.to_string());
+        for _ in 0..10000 {
+            piece_table.insert("a", random::<usize>() % piece_table.length);
+        }
+        let line_count = piece_table.line_count();
+        b.iter(|| {
+            piece_table.line_at(random::<usize>() % line_count);
+        });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,93 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BatchSize};
+use rand::random;
+use rstext::text_buffer::piece_table::PieceTable;
+use rstext::text_buffer::{TextBuffer};
+
+const TEXT: &str = include_str!("large.txt");
+const SMALL_RANGE: usize = 7;
+const LARGE_RANGE: usize = 4096;
+
+fn fragmented_piece_table() -> PieceTable {
+    let mut piece_table = PieceTable::new(TEXT.to_string());
+    for _ in 0..1000 {
+        piece_table.insert("a", random::<usize>() % piece_table.length);
+    }
+
+    piece_table
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("remove_random_char", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                let offset = random::<usize>() % piece_table.length;
+                piece_table.remove(offset..offset + 1);
+            },
+            BatchSize::LargeInput
+        );
+    });
+    c.bench_function("remove_random_small_range", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                let offset = random::<usize>() % (piece_table.length - SMALL_RANGE);
+                piece_table.remove(offset..offset + SMALL_RANGE);
+            },
+            BatchSize::LargeInput
+        );
+    });
+    c.bench_function("remove_random_large_range", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                let offset = random::<usize>() % (piece_table.length - LARGE_RANGE);
+                piece_table.remove(offset..offset + LARGE_RANGE);
+            },
+            BatchSize::LargeInput
+        );
+    });
+
+    c.bench_function("remove_start_large_range", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                piece_table.remove(0..LARGE_RANGE);
+            },
+            BatchSize::LargeInput
+        );
+    });
+    c.bench_function("remove_middle_large_range", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                let offset = piece_table.length / 2;
+                piece_table.remove(offset..offset + LARGE_RANGE);
+            },
+            BatchSize::LargeInput
+        );
+    });
+    c.bench_function("remove_end_large_range", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                let length = piece_table.length;
+                piece_table.remove(length - LARGE_RANGE..length);
+            },
+            BatchSize::LargeInput
+        );
+    });
+    c.bench_function("remove_all", |b| {
+        b.iter_batched(
+            || fragmented_piece_table(),
+            |mut piece_table| {
+                let length = piece_table.length;
+                piece_table.remove(0..length);
+            },
+            BatchSize::LargeInput
+        );
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+use crate::backend::memory::MemoryBackend;
+use crate::backend::{Backend, Style};
+
+/// Draws each frame to a grid of cells, then on flush sends only the cells which differ from the
+/// previous frame on to `inner`, as runs of identically styled content.
+pub struct DiffBackend<B: Backend> {
+    inner: B,
+    previous: MemoryBackend,
+    current: MemoryBackend,
+}
+
+impl<B: Backend> DiffBackend<B> {
+    pub fn new(inner: B) -> DiffBackend<B> {
+        DiffBackend {
+            inner,
+            previous: MemoryBackend::new(0, 0),
+            current: MemoryBackend::new(0, 0),
+        }
+    }
+
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// Sends the cells of `row` which differ from the previous frame on to `inner`.
+    fn flush_row(&mut self, row: u16) {
+        let (width, _) = self.current.size();
+        // Column `inner` will print at next, if known, to avoid redundant moves
+        let mut position = None;
+        let mut column = 0;
+        while column < width {
+            if self.current.cell(column, row) == self.previous.cell(column, row) {
+                column += 1;
+                continue;
+            }
+
+            let start = column;
+            let style = self.current.cell(start, row).style;
+            let mut run = String::new();
+            while column < width {
+                let cell = self.current.cell(column, row);
+                if cell.style != style || cell == self.previous.cell(column, row) {
+                    break;
+                }
+                run.push_str(&cell.content);
+                column += 1;
+            }
+
+            if position != Some(start) {
+                self.inner.move_to(start, row);
+            }
+            self.inner.print(&run, style);
+            position = Some(column);
+        }
+    }
+}
+
+impl<B: Backend> Backend for DiffBackend<B> {
+    fn size(&self) -> (u16, u16) {
+        self.inner.size()
+    }
+
+    fn clear(&mut self) {
+        let (width, height) = self.inner.size();
+        if self.current.size() != (width, height) {
+            // Nothing on screen can be relied upon after a resize, so start over from blank
+            self.inner.clear();
+            self.previous = MemoryBackend::new(width, height);
+            self.current = MemoryBackend::new(width, height);
+        }
+        self.current.clear();
+    }
+
+    fn hide_cursor(&mut self) {
+        self.current.hide_cursor();
+    }
+
+    fn show_cursor(&mut self, column: u16, row: u16) {
+        self.current.show_cursor(column, row);
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) {
+        self.current.move_to(column, row);
+    }
+
+    fn print(&mut self, content: &str, style: Style) {
+        self.current.print(content, style);
+    }
+
+    fn flush(&mut self) {
+        // Hide the cursor while drawing so that it does not visibly jump around the screen
+        self.inner.hide_cursor();
+        let (_, height) = self.current.size();
+        for row in 0..height {
+            self.flush_row(row);
+        }
+        if let Some((column, row)) = self.current.cursor() {
+            self.inner.show_cursor(column, row);
+        }
+        self.inner.flush();
+
+        std::mem::swap(&mut self.previous, &mut self.current);
+        let (width, height) = self.previous.size();
+        self.current = MemoryBackend::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::Color;
+    use unicode_width::UnicodeWidthStr;
+
+    /// Records what is sent to it, as (column, row, content) for each print.
+    struct RecordingBackend {
+        position: (u16, u16),
+        prints: Vec<(u16, u16, String)>,
+        clears: usize,
+    }
+
+    impl Backend for RecordingBackend {
+        fn size(&self) -> (u16, u16) {
+            (8, 2)
+        }
+
+        fn clear(&mut self) {
+            self.clears += 1;
+        }
+
+        fn hide_cursor(&mut self) {}
+
+        fn show_cursor(&mut self, _column: u16, _row: u16) {}
+
+        fn move_to(&mut self, column: u16, row: u16) {
+            self.position = (column, row);
+        }
+
+        fn print(&mut self, content: &str, _style: Style) {
+            let (column, row) = self.position;
+            self.prints.push((column, row, String::from(content)));
+            self.position = (column + content.width() as u16, row);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn draw(backend: &mut DiffBackend<RecordingBackend>, rows: &[(&str, Style)]) {
+        backend.clear();
+        for (row, (content, style)) in rows.iter().enumerate() {
+            backend.move_to(0, row as u16);
+            backend.print(content, *style);
+        }
+        backend.flush();
+    }
+
+    #[test]
+    fn only_changed_cells_sent() {
+        let backend = &mut DiffBackend::new(RecordingBackend {
+            position: (0, 0),
+            prints: Vec::new(),
+            clears: 0,
+        });
+
+        draw(backend, &[("abc", Style::new()), ("def", Style::new())]);
+        assert_eq!(
+            vec![(0, 0, String::from("abc")), (0, 1, String::from("def"))],
+            backend.inner_mut().prints
+        );
+
+        backend.inner_mut().prints.clear();
+        draw(backend, &[("abc", Style::new()), ("dxyf", Style::new())]);
+        assert_eq!(vec![(1, 1, String::from("xyf"))], backend.inner_mut().prints);
+
+        backend.inner_mut().prints.clear();
+        draw(backend, &[("abc", Style::new()), ("d", Style::new())]);
+        assert_eq!(vec![(1, 1, String::from("   "))], backend.inner_mut().prints);
+        assert_eq!(1, backend.inner_mut().clears);
+    }
+
+    #[test]
+    fn runs_split_by_style() {
+        let backend = &mut DiffBackend::new(RecordingBackend {
+            position: (0, 0),
+            prints: Vec::new(),
+            clears: 0,
+        });
+
+        backend.clear();
+        backend.print("ab", Style::new());
+        backend.print("cd", Style::new().with(Color::Blue));
+        backend.move_to(6, 0);
+        backend.print("ef", Style::new());
+        backend.flush();
+
+        assert_eq!(
+            vec![
+                (0, 0, String::from("ab")),
+                (2, 0, String::from("cd")),
+                (6, 0, String::from("ef")),
+            ],
+            backend.inner_mut().prints
+        );
+    }
+}
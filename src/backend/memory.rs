@@ -0,0 +1,151 @@
+use crate::backend::{Backend, Style};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A single column of the screen. A grapheme wider than one column is held by the first of the
+/// cells it covers, the rest being left empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cell {
+    pub content: String,
+    pub style: Style,
+}
+
+impl Cell {
+    fn blank() -> Cell {
+        Cell {
+            content: String::from(" "),
+            style: Style::new(),
+        }
+    }
+}
+
+/// Draws to a grid of cells in memory, so that what would be shown on screen can be inspected.
+pub struct MemoryBackend {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    /// Position subsequent content is printed at, as (column, row).
+    position: (u16, u16),
+    /// Position the cursor is shown at, if it is visible.
+    cursor: Option<(u16, u16)>,
+}
+
+impl MemoryBackend {
+    pub fn new(width: u16, height: u16) -> MemoryBackend {
+        MemoryBackend {
+            width,
+            height,
+            cells: vec![Cell::blank(); width as usize * height as usize],
+            position: (0, 0),
+            cursor: None,
+        }
+    }
+
+    pub fn cell(&self, column: u16, row: u16) -> &Cell {
+        &self.cells[row as usize * self.width as usize + column as usize]
+    }
+
+    /// Content of every cell on `row`, including trailing blanks.
+    pub fn row(&self, row: u16) -> String {
+        (0..self.width)
+            .map(|column| self.cell(column, row).content.as_str())
+            .collect()
+    }
+
+    /// Position the cursor is shown at as (column, row), if it is visible.
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        self.cursor
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            *cell = Cell::blank();
+        }
+    }
+
+    fn hide_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    fn show_cursor(&mut self, column: u16, row: u16) {
+        self.cursor = Some((column, row));
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) {
+        self.position = (column, row);
+    }
+
+    fn print(&mut self, content: &str, style: Style) {
+        let (mut column, row) = self.position;
+        for grapheme in content.graphemes(true) {
+            let width = grapheme.width() as u16;
+            if width == 0 {
+                continue;
+            }
+            // Content running off the edge of the screen is clipped rather than wrapped
+            if column.saturating_add(width) <= self.width && row < self.height {
+                let index = row as usize * self.width as usize + column as usize;
+                self.cells[index] = Cell {
+                    content: String::from(grapheme),
+                    style,
+                };
+                for covered in self.cells[index + 1..index + width as usize].iter_mut() {
+                    *covered = Cell {
+                        content: String::new(),
+                        style,
+                    };
+                }
+            }
+            column = column.saturating_add(width);
+        }
+        self.position = (column, row);
+    }
+
+    fn flush(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::Color;
+
+    #[test]
+    fn print_clipped_at_edge() {
+        let backend = &mut MemoryBackend::new(5, 2);
+        backend.move_to(3, 1);
+        backend.print("abc", Style::new().with(Color::Blue));
+
+        assert_eq!("     ", backend.row(0));
+        assert_eq!("   ab", backend.row(1));
+        assert_eq!(Color::Blue, backend.cell(4, 1).style.foreground);
+    }
+
+    #[test]
+    fn wide_grapheme_covers_cells() {
+        let backend = &mut MemoryBackend::new(4, 1);
+        backend.print("中e\u{301}中", Style::new());
+
+        assert_eq!("中e\u{301} ", backend.row(0));
+        assert_eq!("", backend.cell(1, 0).content);
+        assert_eq!("e\u{301}", backend.cell(2, 0).content);
+        // The second wide character does not fit in the remaining column
+        assert_eq!(" ", backend.cell(3, 0).content);
+    }
+
+    #[test]
+    fn print_advances_position() {
+        let backend = &mut MemoryBackend::new(6, 1);
+        backend.print("ab", Style::new());
+        backend.print("cd", Style::new().reversed());
+
+        assert_eq!("abcd  ", backend.row(0));
+        assert!(!backend.cell(1, 0).style.reverse);
+        assert!(backend.cell(2, 0).style.reverse);
+    }
+}
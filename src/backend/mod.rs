@@ -0,0 +1,59 @@
+pub mod diff;
+pub mod memory;
+pub mod terminal;
+
+use crossterm::style::Color;
+
+/// Colours and attributes a piece of content is drawn with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Style {
+    pub foreground: Color,
+    pub background: Color,
+    pub reverse: bool,
+}
+
+impl Style {
+    pub fn new() -> Style {
+        Style {
+            foreground: Color::Reset,
+            background: Color::Reset,
+            reverse: false,
+        }
+    }
+
+    pub fn with(mut self, foreground: Color) -> Style {
+        self.foreground = foreground;
+        self
+    }
+
+    pub fn on(mut self, background: Color) -> Style {
+        self.background = background;
+        self
+    }
+
+    pub fn reversed(mut self) -> Style {
+        self.reverse = true;
+        self
+    }
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style::new()
+    }
+}
+
+/// A screen the renderer draws to.
+pub trait Backend {
+    /// Size of the screen as (columns, rows).
+    fn size(&self) -> (u16, u16);
+    fn clear(&mut self);
+    fn hide_cursor(&mut self);
+    /// Shows the cursor at `column` and `row`.
+    fn show_cursor(&mut self, column: u16, row: u16);
+    /// Moves the position subsequent content is printed at.
+    fn move_to(&mut self, column: u16, row: u16);
+    /// Prints `content` at the current position, advancing it by the display width of `content`.
+    fn print(&mut self, content: &str, style: Style);
+    fn flush(&mut self);
+}
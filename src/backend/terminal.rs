@@ -0,0 +1,104 @@
+use crate::backend::{Backend, Style};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute, queue,
+    style::{Attribute, PrintStyledContent, Stylize},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io::{stdout, Stdout, Write};
+
+/// Draws to the terminal through crossterm, queueing escape codes until flushed.
+pub struct TerminalBackend {
+    screen: Stdout,
+    /// Columns and rows of the terminal, kept up to date by `resize` rather than queried each frame.
+    size: (u16, u16),
+    /// Whether `enter` has taken over the terminal, and `leave` has yet to hand it back.
+    entered: bool,
+}
+
+impl TerminalBackend {
+    pub fn new() -> TerminalBackend {
+        TerminalBackend {
+            screen: stdout(),
+            size: terminal::size().unwrap_or((0, 0)),
+            entered: false,
+        }
+    }
+
+    /// Records the terminal's new size, as reported by a resize event.
+    pub fn resize(&mut self, columns: u16, rows: u16) {
+        self.size = (columns, rows);
+    }
+
+    /// Switches to the alternate screen in raw mode, capturing the mouse and with pastes
+    /// bracketed so that they arrive as a single event.
+    pub fn enter(&mut self) {
+        let _ = execute!(
+            self.screen,
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            EnableMouseCapture
+        );
+        let _ = terminal::enable_raw_mode();
+        self.entered = true;
+    }
+
+    /// Restores the screen and mode the terminal was in before `enter`, if it was entered.
+    pub fn leave(&mut self) {
+        if !self.entered {
+            return;
+        }
+        self.entered = false;
+        let _ = execute!(
+            self.screen,
+            DisableMouseCapture,
+            DisableBracketedPaste,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Default for TerminalBackend {
+    fn default() -> TerminalBackend {
+        TerminalBackend::new()
+    }
+}
+
+impl Backend for TerminalBackend {
+    fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    fn clear(&mut self) {
+        let _ = queue!(self.screen, Clear(ClearType::All));
+    }
+
+    fn hide_cursor(&mut self) {
+        let _ = queue!(self.screen, Hide);
+    }
+
+    fn show_cursor(&mut self, column: u16, row: u16) {
+        let _ = queue!(self.screen, MoveTo(column, row), Show);
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) {
+        let _ = queue!(self.screen, MoveTo(column, row));
+    }
+
+    fn print(&mut self, content: &str, style: Style) {
+        let styled = crossterm::style::style(content)
+            .with(style.foreground)
+            .on(style.background);
+        let styled = match style.reverse {
+            true => styled.attribute(Attribute::Reverse),
+            false => styled,
+        };
+        let _ = queue!(self.screen, PrintStyledContent(styled));
+    }
+
+    fn flush(&mut self) {
+        self.screen.flush().unwrap();
+    }
+}
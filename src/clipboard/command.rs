@@ -0,0 +1,76 @@
+use crate::clipboard::Clipboard;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Copies and pastes through external programs such as `xclip` or `pbcopy`/`pbpaste`. Each
+/// command is a program followed by its arguments.
+pub struct CommandClipboard {
+    copy: Vec<String>,
+    paste: Vec<String>,
+}
+
+impl CommandClipboard {
+    pub fn new(copy: Vec<String>, paste: Vec<String>) -> CommandClipboard {
+        CommandClipboard { copy, paste }
+    }
+}
+
+fn command(program_and_args: &[String]) -> Option<Command> {
+    let (program, args) = program_and_args.split_first()?;
+    let mut command = Command::new(program);
+    command.args(args).stderr(Stdio::null());
+    Some(command)
+}
+
+impl Clipboard for CommandClipboard {
+    /// Pipes `text` to the copy program's standard input.
+    fn copy(&mut self, text: &str) {
+        let mut command = match command(&self.copy) {
+            Some(command) => command,
+            None => return,
+        };
+        let child = command.stdin(Stdio::piped()).stdout(Stdio::null()).spawn();
+        if let Ok(mut child) = child {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let _ = child.wait();
+        }
+    }
+
+    /// Reads the paste program's standard output, provided it exits successfully.
+    fn paste(&mut self) -> Option<String> {
+        let output = command(&self.paste)?.stdin(Stdio::null()).output().ok()?;
+        match output.status.success() {
+            true => String::from_utf8(output.stdout).ok(),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| String::from(*a)).collect()
+    }
+
+    #[test]
+    fn paste_reads_standard_output() {
+        let mut clipboard = CommandClipboard::new(args(&["true"]), args(&["printf", "a\nb"]));
+        clipboard.copy("ignored");
+
+        assert_eq!(Some(String::from("a\nb")), clipboard.paste());
+    }
+
+    #[test]
+    fn failed_or_missing_commands_paste_nothing() {
+        let mut clipboard = CommandClipboard::new(Vec::new(), args(&["false"]));
+        clipboard.copy("ignored");
+        assert_eq!(None, clipboard.paste());
+
+        let mut clipboard = CommandClipboard::new(Vec::new(), Vec::new());
+        assert_eq!(None, clipboard.paste());
+    }
+}
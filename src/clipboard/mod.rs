@@ -0,0 +1,27 @@
+pub mod command;
+pub mod null;
+pub mod osc52;
+
+use crate::clipboard::command::CommandClipboard;
+use crate::clipboard::null::NullClipboard;
+use crate::clipboard::osc52::Osc52Clipboard;
+use crate::config::ClipboardProvider;
+use std::io::stdout;
+
+/// The clipboard shared with other programs, which copies are sent to and pastes may come from.
+pub trait Clipboard {
+    /// Places `text` on the clipboard.
+    fn copy(&mut self, text: &str);
+    /// Text on the clipboard, if it can be read.
+    fn paste(&mut self) -> Option<String>;
+}
+
+pub fn from_config(provider: &ClipboardProvider) -> Box<dyn Clipboard> {
+    match provider {
+        ClipboardProvider::None => Box::new(NullClipboard),
+        ClipboardProvider::Osc52 => Box::new(Osc52Clipboard::new(stdout())),
+        ClipboardProvider::Command { copy, paste } => {
+            Box::new(CommandClipboard::new(copy.clone(), paste.clone()))
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use crate::clipboard::Clipboard;
+
+/// Discards copies and never has anything to paste, leaving the kill ring as the only clipboard.
+pub struct NullClipboard;
+
+impl Clipboard for NullClipboard {
+    fn copy(&mut self, _text: &str) {}
+
+    fn paste(&mut self) -> Option<String> {
+        None
+    }
+}
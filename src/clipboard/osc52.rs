@@ -0,0 +1,72 @@
+use crate::clipboard::Clipboard;
+use std::io::Write;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copies by writing an OSC 52 escape sequence, which the terminal passes on to the clipboard of
+/// the machine it runs on, including when the editor itself runs over SSH. Terminals rarely allow
+/// the clipboard to be read back, so pasting is not supported.
+pub struct Osc52Clipboard<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Osc52Clipboard<W> {
+    pub fn new(writer: W) -> Osc52Clipboard<W> {
+        Osc52Clipboard { writer }
+    }
+}
+
+impl<W: Write> Clipboard for Osc52Clipboard<W> {
+    fn copy(&mut self, text: &str) {
+        let _ = write!(self.writer, "\x1b]52;c;{}\x07", base64(text.as_bytes()));
+        let _ = self.writer.flush();
+    }
+
+    fn paste(&mut self) -> Option<String> {
+        None
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &b)| group | (b as u32) << (16 - i * 8));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => {
+                    let sextet = (group >> (18 - i * 6)) & 0x3f;
+                    encoded.push(BASE64_ALPHABET[sextet as usize] as char);
+                }
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+    }
+
+    #[test]
+    fn copy_writes_escape_sequence() {
+        let mut clipboard = Osc52Clipboard::new(Vec::new());
+        clipboard.copy("héllo\n");
+
+        assert_eq!(b"\x1b]52;c;aMOpbGxvCg==\x07", &clipboard.writer[..]);
+        assert_eq!(None, clipboard.paste());
+    }
+}
@@ -1,13 +1,160 @@
 use crate::editor::Editor;
 use crate::file;
+use crate::status::MessageKind;
 use crate::text_buffer::TextBuffer;
 
+/// Exits, first asking whether to save any unsaved changes.
 pub fn exit(editor: &mut Editor) {
+    match editor.text_buffer.is_modified() {
+        true => editor.confirming_exit = true,
+        false => editor.running = false,
+    }
+}
+
+/// Saves, then exits unless saving failed.
+pub fn save_and_exit(editor: &mut Editor) {
+    editor.confirming_exit = false;
+    save(editor);
+    if !editor.text_buffer.is_modified() {
+        editor.running = false;
+    }
+}
+
+pub fn discard_and_exit(editor: &mut Editor) {
+    editor.confirming_exit = false;
     editor.running = false;
 }
 
+pub fn cancel_exit(editor: &mut Editor) {
+    editor.confirming_exit = false;
+}
+
+/// Writes the text buffer to its file, reporting the outcome in the status bar.
 pub fn save(editor: &mut Editor) {
-    if let Some(path) = &editor.file_path {
-        file::save(path, editor.text_buffer.all_content());
+    let path = match &editor.file_path {
+        Some(path) => path.clone(),
+        None => {
+            editor.show_message(MessageKind::Error, String::from("No file name to save to"));
+            return;
+        }
+    };
+
+    if editor.read_only {
+        let text = format!("Could not save {}: opened read-only", path.display());
+        editor.show_message(MessageKind::Error, text);
+        return;
+    }
+
+    match file::save(&path, editor.text_buffer.all_content()) {
+        Ok(()) => {
+            editor.text_buffer.mark_saved();
+            let text = format!(
+                "Saved {} ({} lines)",
+                path.display(),
+                editor.text_buffer.line_count()
+            );
+            editor.show_message(MessageKind::Info, text);
+        }
+        Err(e) => {
+            let text = format!("Could not save {}: {}", path.display(), e);
+            editor.show_message(MessageKind::Error, text);
+        }
+    }
+}
+
+pub fn toggle_whitespace(editor: &mut Editor) {
+    editor.config.show_whitespace = !editor.config.show_whitespace;
+}
+
+pub fn toggle_soft_wrap(editor: &mut Editor) {
+    editor.config.soft_wrap = !editor.config.soft_wrap;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::edit;
+    use crate::editor::tests::editor_with;
+
+    /// A running editor with unsaved changes.
+    fn modified_editor() -> Editor {
+        let mut editor = editor_with("ab", &[2]);
+        editor.running = true;
+        edit::insert_character(&mut editor, 'c');
+        editor
+    }
+
+    fn error_message(editor: &Editor) -> Option<&str> {
+        editor
+            .message()
+            .filter(|m| m.kind == MessageKind::Error)
+            .map(|m| m.text.as_str())
+    }
+
+    #[test]
+    fn exit_without_changes_stops_immediately() {
+        let editor = &mut editor_with("ab", &[0]);
+        editor.running = true;
+        exit(editor);
+
+        assert!(!editor.running);
+        assert!(!editor.confirming_exit);
+    }
+
+    #[test]
+    fn exit_with_changes_asks_first() {
+        let editor = &mut modified_editor();
+        exit(editor);
+        assert!(editor.running);
+        assert!(editor.confirming_exit);
+
+        cancel_exit(editor);
+        assert!(editor.running);
+        assert!(!editor.confirming_exit);
+
+        exit(editor);
+        discard_and_exit(editor);
+        assert!(!editor.running);
+        assert!(!editor.confirming_exit);
+        assert!(editor.text_buffer.is_modified());
+    }
+
+    #[test]
+    fn save_and_exit_stays_open_if_not_saved() {
+        let editor = &mut modified_editor();
+        exit(editor);
+        save_and_exit(editor);
+        assert!(editor.running);
+        assert!(!editor.confirming_exit);
+        assert_eq!(Some("No file name to save to"), error_message(editor));
+
+        // Saving over a directory fails
+        let dir = std::env::temp_dir();
+        editor.file_path = Some(dir.clone());
+        exit(editor);
+        save_and_exit(editor);
+        assert!(editor.running);
+        let error = format!("Could not save {}: is a directory", dir.display());
+        assert_eq!(Some(error.as_str()), error_message(editor));
+
+        editor.read_only = true;
+        exit(editor);
+        save_and_exit(editor);
+        assert!(editor.running);
+        assert!(editor.text_buffer.is_modified());
+    }
+
+    #[test]
+    fn save_and_exit_writes_file() {
+        let path = std::env::temp_dir().join(format!("rstext-{}-save-and-exit", std::process::id()));
+        let editor = &mut modified_editor();
+        editor.file_path = Some(path.clone());
+        exit(editor);
+        save_and_exit(editor);
+
+        assert!(!editor.running);
+        assert!(!editor.text_buffer.is_modified());
+        assert_eq!("abc", std::fs::read_to_string(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
     }
 }
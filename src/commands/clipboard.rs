@@ -0,0 +1,317 @@
+use crate::commands::edit;
+use crate::cursor::Cursor;
+use crate::editor::Editor;
+use crate::kill_ring::{Kill, Yank};
+use crate::text_buffer::piece_table::PieceTable;
+use crate::text_buffer::TextBuffer;
+use std::ops::Range;
+
+/// Range of `cursor`'s selection or, if nothing is selected, of the whole line the cursor is on
+/// along with its line break. Returns whether the range is whole lines.
+fn kill_range(text_buffer: &PieceTable, cursor: &Cursor) -> (Range<usize>, bool) {
+    if let Some(selection) = cursor.selection(text_buffer) {
+        return (selection, false);
+    }
+
+    let current_line = text_buffer.line_at(cursor.line);
+    let line_end = current_line.start_index + current_line.len();
+    let range = match cursor.line < text_buffer.line_count() - 1 {
+        true => current_line.start_index..line_end + 1,
+        false => current_line.start_index..line_end,
+    };
+    (range, true)
+}
+
+/// `kill_range` of every cursor in document order, each with the index of its cursor within
+/// `cursors`, or `None` for the primary cursor. Of ranges which overlap, such as the line shared
+/// by two cursors with nothing selected, only one is kept so that its text is killed once,
+/// preferring the primary cursor's.
+fn kill_ranges(editor: &Editor) -> Vec<(Range<usize>, bool, Option<usize>)> {
+    let mut kills = std::iter::once((None, &editor.cursor))
+        .chain(editor.cursors.iter().enumerate().map(|(i, c)| (Some(i), c)))
+        .map(|(index, cursor)| {
+            let (range, linewise) = kill_range(&editor.text_buffer, cursor);
+            (range, linewise, index)
+        })
+        .collect::<Vec<_>>();
+    kills.sort_by_key(|(range, _, index)| (range.start, index.is_some()));
+
+    let mut merged: Vec<(Range<usize>, bool, Option<usize>)> = Vec::new();
+    for kill in kills {
+        match merged.last_mut() {
+            Some(last) if kill.0.start < last.0.end || kill.0 == last.0 => {
+                if kill.2.is_none() {
+                    *last = kill;
+                }
+            }
+            _ => merged.push(kill),
+        }
+    }
+    merged
+}
+
+/// Adds the text in `kills`, joined by line breaks, to the kill ring and the shared clipboard.
+/// If every kill is whole lines, a whole last line is given the line break it lacks.
+fn push_kill(editor: &mut Editor, kills: &[(Range<usize>, bool, Option<usize>)]) {
+    let mut text = String::new();
+    for (range, _, _) in kills {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.extend(editor.text_buffer.chunks(range.clone()));
+    }
+    let linewise = kills.iter().all(|(_, linewise, _)| *linewise);
+    if linewise && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    editor.clipboard.copy(&text);
+    editor.kill_ring.push(Kill { text, linewise });
+}
+
+/// Inserts `kill` at the cursor, or above the cursor's line if it is whole lines, replacing any
+/// selection. Returns the range the kill was inserted into.
+fn insert_kill(editor: &mut Editor, kill: &Kill) -> Range<usize> {
+    if let Some(selection) = editor.cursor.selection(&editor.text_buffer) {
+        edit::replace_selection(editor, &kill.text);
+        return selection.start..selection.start + kill.text.len();
+    }
+
+    editor.cursor.anchor = None;
+    let offset = editor.cursor.offset(&editor.text_buffer);
+    let start = match kill.linewise {
+        true => editor.text_buffer.line_at(editor.cursor.line).start_index,
+        false => offset,
+    };
+    editor.text_buffer.insert(&kill.text, start);
+    editor.cursor.move_to_offset(&editor.text_buffer, offset + kill.text.len());
+    start..start + kill.text.len()
+}
+
+/// Copies the selection at every cursor or, where nothing is selected, the cursor's line.
+pub fn copy(editor: &mut Editor) {
+    let kills = kill_ranges(editor);
+    push_kill(editor, &kills);
+}
+
+/// Removes the selection at `editor.cursor` or, if nothing is selected, the cursor's line.
+fn cut_at_cursor(editor: &mut Editor) {
+    match editor.cursor.selection(&editor.text_buffer) {
+        Some(selection) => {
+            editor.cursor.anchor = None;
+            editor.text_buffer.remove(selection.clone());
+            editor.cursor.move_to_offset(&editor.text_buffer, selection.start);
+        }
+        None => edit::delete_line(editor),
+    }
+}
+
+/// Cuts the selection at every cursor or, where nothing is selected, the cursor's line. Cursors
+/// whose text overlaps another's are merged into it.
+pub fn cut(editor: &mut Editor) {
+    let kills = kill_ranges(editor);
+    push_kill(editor, &kills);
+
+    let kept = kills.iter().filter_map(|(_, _, index)| *index).collect::<Vec<usize>>();
+    editor.cursors = std::mem::take(&mut editor.cursors)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| kept.contains(i))
+        .map(|(_, cursor)| cursor)
+        .collect();
+    editor.for_each_cursor(cut_at_cursor);
+}
+
+/// Pastes the newest kill at every cursor, first taking in anything copied to the shared
+/// clipboard by another program.
+pub fn paste(editor: &mut Editor) {
+    if let Some(text) = editor.clipboard.paste() {
+        let is_newest = editor.kill_ring.get(0).map(|k| &k.text) == Some(&text);
+        if !is_newest && !text.is_empty() {
+            editor.kill_ring.push(Kill { text, linewise: false });
+        }
+    }
+
+    let kill = match editor.kill_ring.get(0) {
+        Some(kill) => kill.clone(),
+        None => return,
+    };
+
+    let is_single_cursor = editor.cursors.is_empty();
+    let ranges = std::cell::RefCell::new(Vec::new());
+    editor.for_each_cursor(|e| ranges.borrow_mut().push(insert_kill(e, &kill)));
+
+    // Only a paste at a single cursor can be swapped for an earlier kill
+    editor.kill_ring.yank = match (is_single_cursor, ranges.into_inner().pop()) {
+        (true, Some(range)) => Some(Yank { range, index: 0 }),
+        _ => None,
+    };
+}
+
+/// Swaps the text just pasted for the kill before it in the ring, wrapping around to the newest.
+pub fn paste_previous(editor: &mut Editor) {
+    let yank = match editor.kill_ring.yank.take() {
+        Some(yank) => yank,
+        None => return,
+    };
+
+    // Only swap if the pasted text has been left as it was
+    let pasted = editor.text_buffer.chunks(yank.range.clone()).collect::<String>();
+    let is_unchanged = editor.kill_ring.get(yank.index).map(|k| &k.text) == Some(&pasted);
+    if !is_unchanged || !editor.cursors.is_empty() {
+        return;
+    }
+
+    let index = (yank.index + 1) % editor.kill_ring.len();
+    let text = editor.kill_ring.get(index).unwrap().text.clone();
+    let offset = editor.cursor.offset(&editor.text_buffer);
+    let start = yank.range.start;
+    editor.text_buffer.begin_revision();
+    editor.text_buffer.remove(yank.range.clone());
+    editor.text_buffer.insert(&text, start);
+    editor.text_buffer.end_revision();
+
+    let offset = match offset >= yank.range.end {
+        true => offset - yank.range.len() + text.len(),
+        false => offset,
+    };
+    editor.cursor.anchor = None;
+    editor.cursor.move_to_offset(&editor.text_buffer, offset);
+    editor.kill_ring.yank = Some(Yank {
+        range: start..start + text.len(),
+        index,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::tests::{cursor_offsets, editor_with};
+
+    /// Selects from `anchor` to each cursor of `editor`, in the order `editor_with` placed them.
+    fn select_from(editor: &mut Editor, anchors: &[usize]) {
+        editor.cursor.anchor = Some(anchors[0]);
+        for (cursor, anchor) in editor.cursors.iter_mut().zip(&anchors[1..]) {
+            cursor.anchor = Some(*anchor);
+        }
+    }
+
+    fn kill(text: &str, linewise: bool) -> Kill {
+        Kill {
+            text: String::from(text),
+            linewise,
+        }
+    }
+
+    #[test]
+    fn copy_joins_selections_in_document_order() {
+        let editor = &mut editor_with("one two three", &[13, 3]);
+        select_from(editor, &[8, 0]);
+        copy(editor);
+
+        assert_eq!(Some(&kill("one\nthree", false)), editor.kill_ring.get(0));
+        assert_eq!("one two three", editor.text_buffer.all_content());
+        assert_eq!(vec![13, 3], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn copy_lines_shared_by_cursors_once() {
+        let editor = &mut editor_with("ab\ncd\nef", &[2, 1, 7]);
+        copy(editor);
+
+        assert_eq!(Some(&kill("ab\nef\n", true)), editor.kill_ring.get(0));
+        assert_eq!(3, cursor_offsets(editor).len());
+    }
+
+    #[test]
+    fn cut_removes_every_selection_as_one_revision() {
+        let editor = &mut editor_with("one two three", &[13, 3]);
+        select_from(editor, &[8, 0]);
+        cut(editor);
+
+        assert_eq!(Some(&kill("one\nthree", false)), editor.kill_ring.get(0));
+        assert_eq!(" two ", editor.text_buffer.all_content());
+        assert_eq!(vec![5, 0], cursor_offsets(editor));
+
+        edit::undo(editor);
+        assert_eq!("one two three", editor.text_buffer.all_content());
+    }
+
+    #[test]
+    fn cut_lines_and_paste_them_above() {
+        let editor = &mut editor_with("ab\ncd\nef", &[4, 1, 2]);
+        cut(editor);
+
+        assert_eq!(Some(&kill("ab\ncd\n", true)), editor.kill_ring.get(0));
+        assert_eq!("ef", editor.text_buffer.all_content());
+        assert_eq!(vec![1], cursor_offsets(editor));
+
+        paste(editor);
+        assert_eq!("ab\ncd\nef", editor.text_buffer.all_content());
+        assert_eq!(vec![7], cursor_offsets(editor));
+
+        // The last line has no line break of its own to cut
+        let editor = &mut editor_with("ab\ncd", &[4]);
+        cut(editor);
+        assert_eq!(Some(&kill("cd\n", true)), editor.kill_ring.get(0));
+        assert_eq!("ab", editor.text_buffer.all_content());
+    }
+
+    #[test]
+    fn paste_at_every_cursor() {
+        let editor = &mut editor_with("ab\ncd", &[4, 1]);
+        editor.kill_ring.push(kill("xy", false));
+        paste(editor);
+
+        assert_eq!("axyb\ncxyd", editor.text_buffer.all_content());
+        assert_eq!(vec![8, 3], cursor_offsets(editor));
+        assert!(editor.kill_ring.yank.is_none());
+
+        edit::undo(editor);
+        assert_eq!("ab\ncd", editor.text_buffer.all_content());
+    }
+
+    #[test]
+    fn paste_replaces_selection() {
+        let editor = &mut editor_with("one two", &[7]);
+        select_from(editor, &[4]);
+        editor.kill_ring.push(kill("three", false));
+        paste(editor);
+
+        assert_eq!("one three", editor.text_buffer.all_content());
+        assert_eq!(vec![9], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn paste_previous_cycles_through_kills() {
+        let editor = &mut editor_with("ab", &[1]);
+        editor.kill_ring.push(kill("one", false));
+        editor.kill_ring.push(kill("two\n", true));
+        paste(editor);
+        assert_eq!("two\nab", editor.text_buffer.all_content());
+        assert_eq!(vec![5], cursor_offsets(editor));
+
+        paste_previous(editor);
+        assert_eq!("oneab", editor.text_buffer.all_content());
+        assert_eq!(vec![4], cursor_offsets(editor));
+        paste_previous(editor);
+        assert_eq!("two\nab", editor.text_buffer.all_content());
+        assert_eq!(vec![5], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn paste_previous_only_after_paste_left_unchanged() {
+        let editor = &mut editor_with("ab", &[1]);
+        editor.kill_ring.push(kill("one", false));
+        editor.kill_ring.push(kill("two", false));
+        paste_previous(editor);
+        assert_eq!("ab", editor.text_buffer.all_content());
+
+        paste(editor);
+        edit::insert_character(editor, 'x');
+        edit::delete_backward(editor);
+        editor.cursor.move_to_offset(&editor.text_buffer, 0);
+        edit::insert_character(editor, 'x');
+        paste_previous(editor);
+        assert_eq!("xatwob", editor.text_buffer.all_content());
+    }
+}
@@ -1,12 +1,51 @@
+use crate::cursor::Cursor;
 use crate::editor::Editor;
+use crate::grapheme::Grapheme;
+use crate::search;
 use crate::str_utils;
 use crate::text_buffer::piece_table::PieceTable;
 use crate::text_buffer::TextBuffer;
+use crate::wrap;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
 
-pub fn cursor_backward(editor: &mut Editor) {
+/// Cursor on line `line_index` before grapheme cluster `character`, or the end of the line if it
+/// is shorter.
+fn cursor_at_character(text_buffer: &dyn TextBuffer, line_index: usize, character: usize) -> Cursor {
+    let line = text_buffer.line_at(line_index);
+    let (byte_offset, character) = match line.content.grapheme_indices(true).nth(character) {
+        Some((byte_offset, _)) => (byte_offset, character),
+        None => (line.len(), str_utils::grapheme_count(&line.content)),
+    };
+
+    Cursor {
+        line: line_index,
+        character,
+        byte_offset,
+        anchor: None,
+        desired_column: None,
+    }
+}
+
+/// Runs a cursor movement, first anchoring a selection at the current position if there is none.
+fn extend_selection(editor: &mut Editor, movement: fn(&mut Editor)) {
+    if editor.cursor.anchor.is_none() {
+        editor.cursor.anchor = Some(editor.cursor.offset(&editor.text_buffer));
+    }
+    movement(editor);
+}
+
+/// Runs a cursor movement, discarding any selection.
+fn collapse_selection(editor: &mut Editor, movement: fn(&mut Editor)) {
+    editor.cursor.anchor = None;
+    movement(editor);
+}
+
+fn move_backward(editor: &mut Editor) {
+    editor.cursor.desired_column = None;
     let current_line = editor.text_buffer.line_at(editor.cursor.line);
-    let previous_char_idx = str_utils::prev_char_idx(&current_line.content, editor.cursor.byte_offset);
-    match previous_char_idx {
+    let previous_grapheme_idx = str_utils::prev_grapheme_idx(&current_line.content, editor.cursor.byte_offset);
+    match previous_grapheme_idx {
         Some(i) => {
             editor.cursor.byte_offset = i;
             editor.cursor.character -= 1;
@@ -15,39 +54,79 @@ pub fn cursor_backward(editor: &mut Editor) {
             if editor.cursor.line > 0 {
                 let line_above = editor.text_buffer.line_at(editor.cursor.line - 1);
                 editor.cursor.byte_offset = line_above.content.len();
-                editor.cursor.character = line_above.content.chars().count();
+                editor.cursor.character = str_utils::grapheme_count(&line_above.content);
                 editor.cursor.line -= 1;
             }
         }
     }
 }
 
-pub fn cursor_down(editor: &mut Editor) {
-    if editor.cursor.line < editor.text_buffer.line_count() - 1 {
-        let line_below = editor.text_buffer.line_at(editor.cursor.line + 1);
-        if line_below.len() < editor.cursor.byte_offset
-        {
-            editor.cursor.byte_offset = line_below.len();
-            editor.cursor.character = line_below.content.chars().count();
+/// Moves the cursor to line `line_index` before grapheme cluster `character`, keeping any
+/// selection anchor.
+pub fn move_to_character(editor: &mut Editor, line_index: usize, character: usize) {
+    let anchor = editor.cursor.anchor;
+    editor.cursor = cursor_at_character(&editor.text_buffer, line_index, character);
+    editor.cursor.anchor = anchor;
+}
+
+/// Graphemes of line `line_index` and the rows they are shown on, which are only more than one if
+/// the line is wrapped.
+fn line_rows(editor: &Editor, line_index: usize) -> (Vec<Grapheme>, Vec<Range<usize>>) {
+    let line = editor.text_buffer.line_at(line_index);
+    let graphemes = Grapheme::from_line(&line, editor.config.tab_width, editor.config.escape);
+    let rows = match editor.config.soft_wrap {
+        true => wrap::wrap(&graphemes, editor.window.width as usize),
+        false => std::iter::once(0..graphemes.len()).collect(),
+    };
+    (graphemes, rows)
+}
+
+/// Moves the cursor up or down by one row, aiming for the desired column within the row, or
+/// otherwise the column the cursor is currently at, which then becomes the desired column.
+fn move_by_row(editor: &mut Editor, down: bool) {
+    let (graphemes, rows) = line_rows(editor, editor.cursor.line);
+    let row = wrap::row_of(&rows, editor.cursor.character);
+    let column = editor
+        .cursor
+        .desired_column
+        .unwrap_or_else(|| wrap::width_of(&graphemes[rows[row].start..editor.cursor.character]));
+
+    let line_index = editor.cursor.line;
+    let (line_index, graphemes, rows, row) = match (down, row) {
+        (true, row) if row + 1 < rows.len() => (line_index, graphemes, rows, row + 1),
+        (false, row) if row > 0 => (line_index, graphemes, rows, row - 1),
+        (true, _) if line_index + 1 < editor.text_buffer.line_count() => {
+            let (graphemes, rows) = line_rows(editor, line_index + 1);
+            (line_index + 1, graphemes, rows, 0)
         }
-        editor.cursor.line += 1;
-    }
+        (false, _) if line_index > 0 => {
+            let (graphemes, rows) = line_rows(editor, line_index - 1);
+            let row = rows.len() - 1;
+            (line_index - 1, graphemes, rows, row)
+        }
+        _ => return,
+    };
+    let character = wrap::character_at_column(&graphemes, &rows, row, column);
+    move_to_character(editor, line_index, character);
+    editor.cursor.desired_column = Some(column);
 }
 
-pub fn cursor_forward(editor: &mut Editor) {
+fn move_down(editor: &mut Editor) {
+    move_by_row(editor, true);
+}
+
+fn move_forward(editor: &mut Editor) {
+    editor.cursor.desired_column = None;
     let current_line = editor.text_buffer.line_at(editor.cursor.line);
 
-    let next_char_idx = str_utils::next_char_idx(&current_line.content, editor.cursor.byte_offset);
-    match next_char_idx {
+    let next_grapheme_idx = str_utils::next_grapheme_idx(&current_line.content, editor.cursor.byte_offset);
+    match next_grapheme_idx {
         Some(i) => {
             editor.cursor.byte_offset = i;
             editor.cursor.character += 1;
         }
         None => {
-            if editor.cursor.byte_offset < current_line.len() {
-                editor.cursor.byte_offset = current_line.len();
-                editor.cursor.character += 1;
-            } else if editor.cursor.line < editor.text_buffer.line_count() - 1 {
+            if editor.cursor.line < editor.text_buffer.line_count() - 1 {
                 editor.cursor.byte_offset = 0;
                 editor.cursor.character = 0;
                 editor.cursor.line += 1;
@@ -56,14 +135,353 @@ pub fn cursor_forward(editor: &mut Editor) {
     }
 }
 
+fn move_up(editor: &mut Editor) {
+    move_by_row(editor, false);
+}
+
+fn move_word_backward(editor: &mut Editor) {
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    match editor.cursor.byte_offset {
+        0 => move_backward(editor),
+        byte_offset => {
+            let word_start = str_utils::prev_word_boundary(&current_line.content, byte_offset, &editor.config.word_characters);
+            editor.cursor.move_to_offset(&editor.text_buffer, current_line.start_index + word_start);
+        }
+    }
+}
+
+fn move_word_forward(editor: &mut Editor) {
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    match editor.cursor.byte_offset {
+        byte_offset if byte_offset == current_line.len() => move_forward(editor),
+        byte_offset => {
+            let word_end = str_utils::next_word_boundary(&current_line.content, byte_offset, &editor.config.word_characters);
+            editor.cursor.move_to_offset(&editor.text_buffer, current_line.start_index + word_end);
+        }
+    }
+}
+
+/// Moves to the first non-whitespace character of the line or, if already there, the start of
+/// the line.
+fn move_line_start(editor: &mut Editor) {
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    let indentation = current_line.len() - current_line.content.trim_start().len();
+    let byte_offset = match editor.cursor.byte_offset == indentation {
+        true => 0,
+        false => indentation,
+    };
+    editor.cursor.move_to_offset(&editor.text_buffer, current_line.start_index + byte_offset);
+}
+
+fn move_line_end(editor: &mut Editor) {
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    editor.cursor.move_to_offset(&editor.text_buffer, current_line.start_index + current_line.len());
+}
+
+/// Moves the cursor a window's height of rows up or down.
+fn move_by_page(editor: &mut Editor, down: bool) {
+    for _ in 0..std::cmp::max(editor.window.height, 1) {
+        move_by_row(editor, down);
+    }
+}
+
+fn move_page_down(editor: &mut Editor) {
+    move_by_page(editor, true);
+}
+
+fn move_page_up(editor: &mut Editor) {
+    move_by_page(editor, false);
+}
+
+/// Runs `command`, which moves each cursor by a page, at every cursor, then scrolls the window by
+/// as many lines as the primary cursor moved.
+fn scroll_by_page(editor: &mut Editor, command: fn(&mut Editor)) {
+    let line = editor.cursor.line;
+    editor.for_each_cursor(command);
+
+    let last_line = editor.text_buffer.line_count() - 1;
+    let window = &mut editor.window;
+    window.vertical_offset = match editor.cursor.line >= line {
+        true => std::cmp::min(window.vertical_offset + (editor.cursor.line - line), last_line),
+        false => window.vertical_offset.saturating_sub(line - editor.cursor.line),
+    };
+}
+
+fn move_document_start(editor: &mut Editor) {
+    editor.cursor.move_to_offset(&editor.text_buffer, 0);
+}
+
+fn move_document_end(editor: &mut Editor) {
+    editor.cursor.move_to_offset(&editor.text_buffer, editor.text_buffer.length);
+}
+
+pub fn cursor_backward(editor: &mut Editor) {
+    collapse_selection(editor, move_backward);
+}
+
+pub fn cursor_down(editor: &mut Editor) {
+    collapse_selection(editor, move_down);
+}
+
+pub fn cursor_forward(editor: &mut Editor) {
+    collapse_selection(editor, move_forward);
+}
+
 pub fn cursor_up(editor: &mut Editor) {
-    if editor.cursor.line > 0 {
-        let line_above = editor.text_buffer.line_at(editor.cursor.line - 1);
-        if line_above.len() < editor.cursor.byte_offset
-        {
-            editor.cursor.byte_offset = line_above.len();
-            editor.cursor.character = line_above.content.chars().count();
+    collapse_selection(editor, move_up);
+}
+
+pub fn cursor_word_backward(editor: &mut Editor) {
+    collapse_selection(editor, move_word_backward);
+}
+
+pub fn cursor_word_forward(editor: &mut Editor) {
+    collapse_selection(editor, move_word_forward);
+}
+
+pub fn cursor_line_start(editor: &mut Editor) {
+    collapse_selection(editor, move_line_start);
+}
+
+pub fn cursor_line_end(editor: &mut Editor) {
+    collapse_selection(editor, move_line_end);
+}
+
+pub fn cursor_page_down(editor: &mut Editor) {
+    scroll_by_page(editor, |e| collapse_selection(e, move_page_down));
+}
+
+pub fn cursor_page_up(editor: &mut Editor) {
+    scroll_by_page(editor, |e| collapse_selection(e, move_page_up));
+}
+
+pub fn cursor_document_start(editor: &mut Editor) {
+    collapse_selection(editor, move_document_start);
+}
+
+pub fn cursor_document_end(editor: &mut Editor) {
+    collapse_selection(editor, move_document_end);
+}
+
+/// Moves to the bracket matching the one at or just before the cursor, if any.
+pub fn cursor_matching_bracket(editor: &mut Editor) {
+    let text_buffer = &editor.text_buffer;
+    let offset = editor.cursor.offset(text_buffer);
+    // Scanning chunks outward from the cursor avoids copying the buffer to find a nearby bracket
+    let forward = text_buffer
+        .chunks(offset..usize::MAX)
+        .flat_map(|chunk| chunk.chars())
+        .scan(offset, |start, c| {
+            let at = *start;
+            *start += c.len_utf8();
+            Some((at, c))
+        });
+    let chunks_before = text_buffer.chunks(0..offset).collect::<Vec<&str>>();
+    let backward = chunks_before
+        .iter()
+        .rev()
+        .flat_map(|chunk| chunk.chars().rev())
+        .scan(offset, |end, c| {
+            *end -= c.len_utf8();
+            Some((*end, c))
+        });
+    if let Some(matching) = str_utils::matching_bracket_around(forward, backward) {
+        editor.cursor.anchor = None;
+        editor.cursor.move_to_offset(&editor.text_buffer, matching);
+    }
+}
+
+pub fn select_backward(editor: &mut Editor) {
+    extend_selection(editor, move_backward);
+}
+
+pub fn select_down(editor: &mut Editor) {
+    extend_selection(editor, move_down);
+}
+
+pub fn select_forward(editor: &mut Editor) {
+    extend_selection(editor, move_forward);
+}
+
+pub fn select_up(editor: &mut Editor) {
+    extend_selection(editor, move_up);
+}
+
+pub fn select_word_backward(editor: &mut Editor) {
+    extend_selection(editor, move_word_backward);
+}
+
+pub fn select_word_forward(editor: &mut Editor) {
+    extend_selection(editor, move_word_forward);
+}
+
+pub fn select_line_start(editor: &mut Editor) {
+    extend_selection(editor, move_line_start);
+}
+
+pub fn select_line_end(editor: &mut Editor) {
+    extend_selection(editor, move_line_end);
+}
+
+pub fn select_page_down(editor: &mut Editor) {
+    scroll_by_page(editor, |e| extend_selection(e, move_page_down));
+}
+
+pub fn select_page_up(editor: &mut Editor) {
+    scroll_by_page(editor, |e| extend_selection(e, move_page_up));
+}
+
+pub fn select_document_start(editor: &mut Editor) {
+    extend_selection(editor, move_document_start);
+}
+
+pub fn select_document_end(editor: &mut Editor) {
+    extend_selection(editor, move_document_end);
+}
+
+pub fn add_cursor_above(editor: &mut Editor) {
+    let top = editor
+        .cursors
+        .iter()
+        .chain(std::iter::once(&editor.cursor))
+        .min_by_key(|c| c.line)
+        .unwrap();
+    if top.line > 0 {
+        let cursor = cursor_at_character(&editor.text_buffer, top.line - 1, top.character);
+        editor.cursors.push(cursor);
+    }
+}
+
+pub fn add_cursor_below(editor: &mut Editor) {
+    let bottom = editor
+        .cursors
+        .iter()
+        .chain(std::iter::once(&editor.cursor))
+        .max_by_key(|c| c.line)
+        .unwrap();
+    if bottom.line < editor.text_buffer.line_count() - 1 {
+        let cursor = cursor_at_character(&editor.text_buffer, bottom.line + 1, bottom.character);
+        editor.cursors.push(cursor);
+    }
+}
+
+/// Selects the word under the primary cursor or, if there is already a selection, adds a cursor
+/// selecting the next occurrence of the selected text after the last cursor.
+pub fn add_cursor_at_next_occurrence(editor: &mut Editor) {
+    let selection = match editor.cursor.selection(&editor.text_buffer) {
+        Some(selection) => selection,
+        None => {
+            let current_line = editor.text_buffer.line_at(editor.cursor.line);
+            let content = &current_line.content;
+            let word_characters = &editor.config.word_characters;
+            let is_word = |word: &Range<usize>| {
+                content[word.clone()]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| str_utils::char_class(c, word_characters) == str_utils::CharClass::Word)
+            };
+            // A word just before the cursor is selected if there is none under it
+            let word = std::iter::once(editor.cursor.byte_offset)
+                .chain(str_utils::prev_grapheme_idx(content, editor.cursor.byte_offset))
+                .map(|byte_offset| str_utils::word_at(content, byte_offset, word_characters))
+                .find(is_word);
+            if let Some(word) = word {
+                editor.cursor.anchor = Some(current_line.start_index + word.start);
+                editor.cursor.move_to_offset(&editor.text_buffer, current_line.start_index + word.end);
+            }
+            return;
+        }
+    };
+
+    let text_buffer = &editor.text_buffer;
+    let needle = text_buffer.chunks(selection).collect::<String>();
+    let last_offset = editor
+        .cursors
+        .iter()
+        .chain(std::iter::once(&editor.cursor))
+        .map(|c| {
+            let offset = c.offset(text_buffer);
+            c.anchor.map_or(offset, |anchor| std::cmp::max(anchor, offset))
+        })
+        .max()
+        .unwrap();
+
+    // Search onwards from the last cursor, wrapping around to the start of the buffer
+    let next_match = search::find_next(text_buffer, &needle, true, last_offset).map(|m| m.start);
+    if let Some(match_start) = next_match {
+        let mut cursor = Cursor::new();
+        cursor.move_to_offset(text_buffer, match_start + needle.len());
+        cursor.anchor = Some(match_start);
+
+        let is_new = editor
+            .cursors
+            .iter()
+            .chain(std::iter::once(&editor.cursor))
+            .all(|c| c.line != cursor.line || c.byte_offset != cursor.byte_offset);
+        if is_new {
+            editor.cursors.push(cursor);
         }
-        editor.cursor.line -= 1;
+    }
+}
+
+pub fn remove_secondary_cursors(editor: &mut Editor) {
+    editor.cursors.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::tests::{cursor_offsets, editor_with};
+
+    #[test]
+    fn page_down_scrolls_window_by_primary_cursor() {
+        let text = (0..20).map(|i| i.to_string()).collect::<Vec<String>>().join("\n");
+        let editor = &mut editor_with(&text, &[0, 4]);
+        editor.window.height = 5;
+
+        cursor_page_down(editor);
+        assert_eq!((5, 7), (editor.cursor.line, editor.cursors[0].line));
+        assert_eq!(5, editor.window.vertical_offset);
+
+        select_page_up(editor);
+        assert_eq!((0, 2), (editor.cursor.line, editor.cursors[0].line));
+        assert_eq!(0, editor.window.vertical_offset);
+        assert!(editor.cursor.anchor.is_some());
+    }
+
+    #[test]
+    fn matching_bracket_across_chunks() {
+        let editor = &mut editor_with("f(a) {}", &[1]);
+        editor.text_buffer.insert("[b]", 3);
+
+        // f(a[b]) {}
+        editor.for_each_cursor(cursor_matching_bracket);
+        assert_eq!(vec![6], cursor_offsets(editor));
+        editor.for_each_cursor(cursor_matching_bracket);
+        assert_eq!(vec![1], cursor_offsets(editor));
+
+        let editor = &mut editor_with("f(a[b]) {}", &[10, 8, 2]);
+        editor.for_each_cursor(cursor_matching_bracket);
+        assert_eq!(vec![8, 6, 9], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn next_occurrence_selects_word_by_word_characters() {
+        let editor = &mut editor_with("foo-bar = foo-bar;", &[1]);
+        editor.config.word_characters = String::from("-");
+        add_cursor_at_next_occurrence(editor);
+        assert_eq!((Some(0), 7), (editor.cursor.anchor, editor.cursor.offset(&editor.text_buffer)));
+
+        add_cursor_at_next_occurrence(editor);
+        assert_eq!(vec![7, 17], cursor_offsets(editor));
+        assert_eq!(Some(10), editor.cursors[0].anchor);
+
+        // A word ending at the cursor is selected, but punctuation is not
+        let editor = &mut editor_with("foo_bar = 1;", &[7]);
+        add_cursor_at_next_occurrence(editor);
+        assert_eq!(Some(0), editor.cursor.anchor);
+        let editor = &mut editor_with("foo_bar = 1;", &[9]);
+        add_cursor_at_next_occurrence(editor);
+        assert_eq!(None, editor.cursor.anchor);
     }
 }
@@ -3,12 +3,84 @@ use crate::editor::Editor;
 use crate::str_utils;
 use crate::text_buffer::piece_table::PieceTable;
 use crate::text_buffer::TextBuffer;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Removes the selected text, if any, leaving the cursor where it started.
+/// Returns whether there was a selection to remove.
+fn delete_selection(editor: &mut Editor) -> bool {
+    let selection = editor.cursor.selection(&editor.text_buffer);
+    editor.cursor.anchor = None;
+    match selection {
+        Some(range) => {
+            let start = range.start;
+            editor.text_buffer.remove(range);
+            editor.cursor.move_to_offset(&editor.text_buffer, start);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Replaces the selected text, if any, with `s` as a single revision, leaving the cursor after
+/// the inserted text. Returns whether there was a selection to replace.
+pub fn replace_selection(editor: &mut Editor, s: &str) -> bool {
+    let selection = editor.cursor.selection(&editor.text_buffer);
+    match selection {
+        Some(range) => {
+            let start = range.start;
+            editor.text_buffer.begin_revision();
+            delete_selection(editor);
+            editor.text_buffer.insert(s, start);
+            editor.text_buffer.end_revision();
+            editor.cursor.move_to_offset(&editor.text_buffer, start + s.len());
+            true
+        }
+        None => {
+            editor.cursor.anchor = None;
+            false
+        }
+    }
+}
+
+/// Inserts `indentation` at the start of every line touched by `selection`, keeping the
+/// selected lines selected.
+fn indent_selection(editor: &mut Editor, selection: Range<usize>, indentation: &str) {
+    let head = editor.cursor.offset(&editor.text_buffer);
+    let text_buffer = &mut editor.text_buffer;
+    let first_line = text_buffer.line_index_at(selection.start);
+    let mut last_line = text_buffer.line_index_at(selection.end);
+    if last_line > first_line && text_buffer.line_at(last_line).start_index == selection.end {
+        // Selection ends at the very start of a line, which is not considered selected
+        last_line -= 1;
+    }
+
+    let line_starts = (first_line..=last_line)
+        .map(|i| text_buffer.line_at(i).start_index)
+        .collect::<Vec<usize>>();
+    text_buffer.begin_revision();
+    for line_start in line_starts.iter().rev() {
+        text_buffer.insert(indentation, *line_start);
+    }
+    text_buffer.end_revision();
+
+    let shift = |offset: usize| {
+        offset + indentation.len() * line_starts.iter().filter(|s| **s < offset).count()
+    };
+    editor.cursor.anchor = editor.cursor.anchor.map(shift);
+    editor.cursor.move_to_offset(&editor.text_buffer, shift(head));
+}
 
 pub fn delete_backward(editor: &mut Editor) {
+    editor.cursor.desired_column = None;
+    if delete_selection(editor) {
+        return;
+    }
+
     if editor.cursor.byte_offset > 0 {
         let current_line = editor.text_buffer.line_at(editor.cursor.line);
-        let prev_char_idx = str_utils::prev_char_idx(&current_line.content, editor.cursor.byte_offset);
-        match prev_char_idx {
+        let prev_grapheme_idx = str_utils::prev_grapheme_idx(&current_line.content, editor.cursor.byte_offset);
+        match prev_grapheme_idx {
             Some(i) => {
                 editor.text_buffer.remove(current_line.start_index + i..current_line.start_index + editor.cursor.byte_offset);
                 editor.cursor.byte_offset = i;
@@ -24,19 +96,134 @@ pub fn delete_backward(editor: &mut Editor) {
         let line_above = editor.text_buffer.line_at(editor.cursor.line - 1);
         editor.text_buffer.remove(line_above.start_index + line_above.len()..line_above.start_index + line_above.len() + 1);
         editor.cursor.byte_offset = line_above.len();
-        editor.cursor.character = line_above.content.chars().count();
+        editor.cursor.character = str_utils::grapheme_count(&line_above.content);
         editor.cursor.line -= 1;
     }
 }
 
-pub fn insert_character(editor: &mut Editor, c: char) {
+/// Removes `range`, which must not start after the cursor, leaving the cursor at its start.
+fn delete_range(editor: &mut Editor, range: Range<usize>) {
+    let start = range.start;
+    if !range.is_empty() {
+        editor.text_buffer.remove(range);
+    }
+    editor.cursor.move_to_offset(&editor.text_buffer, start);
+}
+
+pub fn delete_forward(editor: &mut Editor) {
+    editor.cursor.desired_column = None;
+    if delete_selection(editor) {
+        return;
+    }
+
+    // Removing text after the cursor leaves its line, byte offset and character unchanged
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    let offset = current_line.start_index + editor.cursor.byte_offset;
+    match str_utils::next_grapheme_idx(&current_line.content, editor.cursor.byte_offset) {
+        Some(i) => editor.text_buffer.remove(offset..current_line.start_index + i),
+        None if editor.cursor.line < editor.text_buffer.line_count() - 1 => {
+            editor.text_buffer.remove(offset..offset + 1);
+        }
+        None => (),
+    }
+}
+
+pub fn delete_word_backward(editor: &mut Editor) {
+    if editor.cursor.selection(&editor.text_buffer).is_some() || editor.cursor.byte_offset == 0 {
+        delete_backward(editor);
+        return;
+    }
+
     let current_line = editor.text_buffer.line_at(editor.cursor.line);
-    editor.text_buffer.insert(&c.to_string(), current_line.start_index + editor.cursor.byte_offset);
-    editor.cursor.byte_offset += c.len_utf8();
-    editor.cursor.character += 1;
+    let word_start = str_utils::prev_word_boundary(&current_line.content, editor.cursor.byte_offset, &editor.config.word_characters);
+    editor.cursor.anchor = None;
+    delete_range(editor, current_line.start_index + word_start..current_line.start_index + editor.cursor.byte_offset);
+}
+
+pub fn delete_word_forward(editor: &mut Editor) {
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    if editor.cursor.selection(&editor.text_buffer).is_some() || editor.cursor.byte_offset == current_line.len() {
+        delete_forward(editor);
+        return;
+    }
+
+    let word_end = str_utils::next_word_boundary(&current_line.content, editor.cursor.byte_offset, &editor.config.word_characters);
+    editor.cursor.anchor = None;
+    delete_range(editor, current_line.start_index + editor.cursor.byte_offset..current_line.start_index + word_end);
+}
+
+/// Removes the rest of the line after the cursor or, if the cursor is already at the end of the
+/// line, the line break joining it with the next.
+pub fn delete_to_line_end(editor: &mut Editor) {
+    let current_line = editor.text_buffer.line_at(editor.cursor.line);
+    if editor.cursor.selection(&editor.text_buffer).is_some() || editor.cursor.byte_offset == current_line.len() {
+        delete_forward(editor);
+        return;
+    }
+
+    editor.cursor.anchor = None;
+    delete_range(editor, current_line.start_index + editor.cursor.byte_offset..current_line.start_index + current_line.len());
+}
+
+/// Removes the line the cursor is on along with its line break, keeping the cursor before the same
+/// grapheme cluster on the line that takes its place where possible.
+pub fn delete_line(editor: &mut Editor) {
+    let line_index = editor.cursor.line;
+    let character = editor.cursor.character;
+    let current_line = editor.text_buffer.line_at(line_index);
+    let line_end = current_line.start_index + current_line.len();
+    let range = match line_index < editor.text_buffer.line_count() - 1 {
+        true => current_line.start_index..line_end + 1,
+        // The last line has no line break of its own, so remove the one before it instead
+        false => current_line.start_index.saturating_sub(1)..line_end,
+    };
+    editor.cursor.anchor = None;
+    delete_range(editor, range);
+
+    let line_index = std::cmp::min(line_index, editor.text_buffer.line_count() - 1);
+    let line = editor.text_buffer.line_at(line_index);
+    let byte_offset = line
+        .content
+        .grapheme_indices(true)
+        .nth(character)
+        .map_or(line.len(), |(i, _)| i);
+    editor.cursor.move_to_offset(&editor.text_buffer, line.start_index + byte_offset);
+}
+
+/// Removes the line each cursor is on, merging cursors that share a line first so that each line
+/// is removed only once.
+pub fn delete_lines(editor: &mut Editor) {
+    let mut lines = vec![editor.cursor.line];
+    editor.cursors.retain(|c| {
+        let is_unique = !lines.contains(&c.line);
+        lines.push(c.line);
+        is_unique
+    });
+    editor.for_each_cursor(delete_line);
+}
+
+pub fn insert_character(editor: &mut Editor, c: char) {
+    insert_text(editor, &c.to_string());
+}
+
+/// Inserts `s` verbatim at the cursor, replacing any selection, as a single edit.
+pub fn insert_text(editor: &mut Editor, s: &str) {
+    if replace_selection(editor, s) {
+        return;
+    }
+
+    // Repositioning by offset accounts for a combining character joining the preceding cluster
+    let offset = editor.cursor.offset(&editor.text_buffer);
+    editor.text_buffer.insert(s, offset);
+    editor.cursor.move_to_offset(&editor.text_buffer, offset + s.len());
 }
 
 pub fn insert_newline(editor: &mut Editor) {
+    editor.cursor.desired_column = None;
+    if replace_selection(editor, "\n") {
+        return;
+    }
+
     let current_line = editor.text_buffer.line_at(editor.cursor.line);
     editor.text_buffer.insert("\n", current_line.start_index + editor.cursor.byte_offset);
     editor.cursor.byte_offset = 0;
@@ -45,14 +232,116 @@ pub fn insert_newline(editor: &mut Editor) {
 }
 
 pub fn insert_tab(editor: &mut Editor) {
+    editor.cursor.desired_column = None;
     let current_line = editor.text_buffer.line_at(editor.cursor.line);
     let to_insert = match editor.config.indentation {
         IndentationPreference::Tabs => String::from("\t"),
         IndentationPreference::Spaces => vec![' '; editor.config.tab_width as usize].into_iter().collect()
     };
 
+    if let Some(selection) = editor.cursor.selection(&editor.text_buffer) {
+        indent_selection(editor, selection, &to_insert);
+        return;
+    }
+
+    editor.cursor.anchor = None;
     editor.text_buffer
-        .insert(&to_insert, current_line.start_index + editor.cursor.character);
+        .insert(&to_insert, current_line.start_index + editor.cursor.byte_offset);
     editor.cursor.byte_offset += to_insert.len();
     editor.cursor.character += to_insert.chars().count();
 }
+
+/// Reapplies the most recently undone revision, leaving a single cursor where it was made.
+pub fn redo(editor: &mut Editor) {
+    editor.cursor.anchor = None;
+    editor.cursors.clear();
+    if let Some(offset) = editor.text_buffer.redo() {
+        editor.cursor.move_to_offset(&editor.text_buffer, offset);
+    }
+}
+
+/// Reverts the most recent revision, leaving a single cursor where it was made, as secondary
+/// cursors are not recorded in the history.
+pub fn undo(editor: &mut Editor) {
+    editor.cursor.anchor = None;
+    editor.cursors.clear();
+    if let Some(offset) = editor.text_buffer.undo() {
+        editor.cursor.move_to_offset(&editor.text_buffer, offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::tests::{cursor_offsets, editor_with};
+
+    /// Text and cursor offsets after running `command` at each of `offsets` in `text`.
+    fn after(command: fn(&mut Editor), text: &str, offsets: &[usize]) -> (String, Vec<usize>) {
+        let editor = &mut editor_with(text, offsets);
+        editor.for_each_cursor(command);
+        (editor.text_buffer.all_content(), cursor_offsets(editor))
+    }
+
+    #[test]
+    fn delete_forward_removes_grapheme_or_line_break() {
+        assert_eq!((String::from("ac\nd"), vec![1]), after(delete_forward, "abc\nd", &[1]));
+        assert_eq!((String::from("abcd"), vec![3]), after(delete_forward, "abc\nd", &[3]));
+        assert_eq!((String::from("abc\nd"), vec![5]), after(delete_forward, "abc\nd", &[5]));
+        assert_eq!((String::from("a"), vec![1]), after(delete_forward, "ae\u{301}", &[1]));
+        assert_eq!((String::new(), vec![0]), after(delete_forward, "", &[0]));
+    }
+
+    #[test]
+    fn delete_word_backward_removes_to_word_start() {
+        let text = "let foo_bar\nx";
+        assert_eq!((String::from("let \nx"), vec![4]), after(delete_word_backward, text, &[11]));
+        assert_eq!((String::from("let foo_barx"), vec![11]), after(delete_word_backward, text, &[12]));
+        assert_eq!((String::from("let foo_bar\n"), vec![12]), after(delete_word_backward, text, &[13]));
+        assert_eq!((String::new(), vec![0]), after(delete_word_backward, "", &[0]));
+    }
+
+    #[test]
+    fn delete_word_forward_removes_to_word_end() {
+        let text = "let foo_bar\nx";
+        assert_eq!((String::from("let \nx"), vec![4]), after(delete_word_forward, text, &[4]));
+        assert_eq!((String::from("let foo_barx"), vec![11]), after(delete_word_forward, text, &[11]));
+        assert_eq!((String::from("let foo_bar\n"), vec![12]), after(delete_word_forward, text, &[12]));
+        assert_eq!((String::from("let foo_bar\nx"), vec![13]), after(delete_word_forward, text, &[13]));
+        assert_eq!((String::new(), vec![0]), after(delete_word_forward, "", &[0]));
+    }
+
+    #[test]
+    fn delete_to_line_end_removes_rest_of_line_or_line_break() {
+        let text = "abc\nde";
+        assert_eq!((String::from("a\nde"), vec![1]), after(delete_to_line_end, text, &[1]));
+        assert_eq!((String::from("abcde"), vec![3]), after(delete_to_line_end, text, &[3]));
+        assert_eq!((String::from("abc\nd"), vec![5]), after(delete_to_line_end, text, &[5]));
+        assert_eq!((String::from("abc\nde"), vec![6]), after(delete_to_line_end, text, &[6]));
+        assert_eq!((String::new(), vec![0]), after(delete_to_line_end, "", &[0]));
+    }
+
+    #[test]
+    fn delete_line_keeps_cursor_column() {
+        let text = "abc\nde\nfghi";
+        assert_eq!((String::from("abc\nfghi"), vec![6]), after(delete_line, text, &[6]));
+        assert_eq!((String::from("de\nfghi"), vec![2]), after(delete_line, text, &[2]));
+        // The last line takes the line break before it, leaving the cursor on the line above
+        assert_eq!((String::from("abc\nde"), vec![6]), after(delete_line, text, &[10]));
+        assert_eq!((String::new(), vec![0]), after(delete_line, "abc", &[1]));
+        assert_eq!((String::new(), vec![0]), after(delete_line, "", &[0]));
+    }
+
+    #[test]
+    fn delete_lines_removes_shared_line_once() {
+        let text = "abc\nde\nfghi";
+        let editor = &mut editor_with(text, &[1, 2]);
+        delete_lines(editor);
+        assert_eq!("de\nfghi", editor.text_buffer.all_content());
+        assert_eq!(vec![1], cursor_offsets(editor));
+
+        let editor = &mut editor_with(text, &[1, 9, 2, 5]);
+        delete_lines(editor);
+        assert_eq!("", editor.text_buffer.all_content());
+        assert_eq!(vec![0], cursor_offsets(editor));
+    }
+}
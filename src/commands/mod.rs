@@ -1,3 +1,7 @@
 pub mod app;
+pub mod clipboard;
 pub mod cursor;
-pub mod edit;
\ No newline at end of file
+pub mod edit;
+pub mod mouse;
+pub mod replace;
+pub mod search;
\ No newline at end of file
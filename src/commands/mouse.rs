@@ -0,0 +1,120 @@
+use crate::commands::cursor;
+use crate::editor::Editor;
+use crate::renderer;
+use crate::str_utils;
+use crate::text_buffer::TextBuffer;
+
+/// Lines scrolled by each step of the mouse wheel.
+const SCROLL_LINES: usize = 3;
+
+/// Moves the cursor to the text shown at terminal `column` and `row`, keeping any selection
+/// anchor, and scrolls the window to follow it again.
+fn move_to_position(editor: &mut Editor, column: u16, row: u16) {
+    let (line, character) =
+        renderer::position_at(&editor.text_buffer, &editor.window, &editor.config, column, row);
+    cursor::move_to_character(editor, line, character);
+    editor.window.follow_cursor = true;
+}
+
+/// Places the cursor at the text clicked, dropping any selection and secondary cursors.
+pub fn click(editor: &mut Editor, column: u16, row: u16) {
+    editor.cursors.clear();
+    editor.cursor.anchor = None;
+    move_to_position(editor, column, row);
+}
+
+/// Extends the selection to the text the mouse is dragged over.
+pub fn drag(editor: &mut Editor, column: u16, row: u16) {
+    if editor.cursor.anchor.is_none() {
+        editor.cursor.anchor = Some(editor.cursor.offset(&editor.text_buffer));
+    }
+    move_to_position(editor, column, row);
+}
+
+/// Selects the word, or run of punctuation or whitespace, that was double-clicked.
+pub fn select_word(editor: &mut Editor, column: u16, row: u16) {
+    click(editor, column, row);
+    let line = editor.text_buffer.line_at(editor.cursor.line);
+    let word = str_utils::word_at(
+        &line.content,
+        editor.cursor.byte_offset,
+        &editor.config.word_characters,
+    );
+    editor.cursor.anchor = Some(line.start_index + word.start);
+    editor.cursor.move_to_offset(&editor.text_buffer, line.start_index + word.end);
+}
+
+/// Scrolls the window without moving the cursor, which may be left out of view.
+fn scroll(editor: &mut Editor, down: bool) {
+    let last_line = editor.text_buffer.line_count() - 1;
+    let window = &mut editor.window;
+    window.vertical_offset = match down {
+        true => std::cmp::min(window.vertical_offset + SCROLL_LINES, last_line),
+        false => window.vertical_offset.saturating_sub(SCROLL_LINES),
+    };
+    window.skipped_rows = 0;
+    window.follow_cursor = false;
+}
+
+pub fn scroll_down(editor: &mut Editor) {
+    scroll(editor, true);
+}
+
+pub fn scroll_up(editor: &mut Editor) {
+    scroll(editor, false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::tests::{cursor_offsets, editor_with};
+
+    fn editor_in_window(text: &str) -> Editor {
+        let mut editor = editor_with(text, &[0]);
+        editor.window.width = 20;
+        editor.window.height = 5;
+        editor
+    }
+
+    #[test]
+    fn drag_selects_from_click() {
+        let editor = &mut editor_in_window("one two\nthree");
+        click(editor, 2, 0);
+        drag(editor, 3, 1);
+        assert_eq!(Some(2..11), editor.cursor.selection(&editor.text_buffer));
+
+        drag(editor, 1, 0);
+        assert_eq!(Some(1..2), editor.cursor.selection(&editor.text_buffer));
+        assert_eq!(vec![1], cursor_offsets(editor));
+
+        click(editor, 5, 0);
+        assert_eq!(None, editor.cursor.selection(&editor.text_buffer));
+    }
+
+    #[test]
+    fn select_word_at_click() {
+        let editor = &mut editor_in_window("let foo_bar = 1;");
+        select_word(editor, 6, 0);
+        assert_eq!(Some(4..11), editor.cursor.selection(&editor.text_buffer));
+        assert_eq!(vec![11], cursor_offsets(editor));
+
+        select_word(editor, 12, 0);
+        assert_eq!(Some(12..13), editor.cursor.selection(&editor.text_buffer));
+    }
+
+    #[test]
+    fn scroll_stops_at_last_line_leaving_cursor() {
+        let editor = &mut editor_in_window("0\n1\n2\n3\n4\n5\n6\n7");
+        editor.window.follow_cursor = true;
+        for _ in 0..3 {
+            scroll_down(editor);
+        }
+        assert_eq!(7, editor.window.vertical_offset);
+        assert!(!editor.window.follow_cursor);
+        assert_eq!(vec![0], cursor_offsets(editor));
+
+        scroll_up(editor);
+        assert_eq!(4, editor.window.vertical_offset);
+        assert_eq!(vec![0], cursor_offsets(editor));
+    }
+}
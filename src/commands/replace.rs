@@ -0,0 +1,186 @@
+use crate::editor::Editor;
+use crate::replace::{self, Replace, ReplaceStage};
+use crate::text_buffer::TextBuffer;
+
+/// Offset just past the character at `offset`, used to step over empty matches.
+fn step_past(text_buffer: &dyn TextBuffer, offset: usize) -> usize {
+    let next_char = text_buffer
+        .chunks(offset..usize::MAX)
+        .flat_map(|chunk| chunk.chars())
+        .next();
+    offset + next_char.map_or(1, |c| c.len_utf8())
+}
+
+/// Selects the next match at or after the replace origin, previewing its replacement, or collapses
+/// the cursor back to the origin if there is none.
+fn update_pending(editor: &mut Editor) {
+    let replace = match &mut editor.replace {
+        Some(replace) => replace,
+        None => return,
+    };
+
+    let text_buffer = &editor.text_buffer;
+    let pending = replace
+        .regex()
+        .and_then(|regex| replace::find_next(text_buffer, regex, &replace.replacement, replace.origin));
+    match &pending {
+        Some(pending) => {
+            editor.cursor.anchor = Some(pending.range.start);
+            editor.cursor.move_to_offset(&editor.text_buffer, pending.range.end);
+        }
+        None => {
+            let origin = std::cmp::min(replace.origin, editor.text_buffer.length);
+            editor.cursor.anchor = None;
+            editor.cursor.move_to_offset(&editor.text_buffer, origin);
+        }
+    }
+    replace.pending = pending;
+}
+
+/// Replaces the pending match and moves on to the one after it.
+fn replace_pending(editor: &mut Editor) {
+    let replace = match &mut editor.replace {
+        Some(replace) => replace,
+        None => return,
+    };
+
+    if let Some(pending) = replace.pending.take() {
+        replace::apply(&mut editor.text_buffer, std::slice::from_ref(&pending));
+        replace.origin = pending.range.start + pending.text.len();
+        if pending.range.is_empty() {
+            // Step past the empty match rather than finding it again
+            replace.origin = step_past(&editor.text_buffer, replace.origin);
+        }
+    }
+    update_pending(editor);
+}
+
+pub fn start_replace(editor: &mut Editor) {
+    editor.cursors.clear();
+    let origin = editor
+        .cursor
+        .selection(&editor.text_buffer)
+        .map(|s| s.start)
+        .unwrap_or_else(|| editor.cursor.offset(&editor.text_buffer));
+    editor.replace = Some(Replace::new(origin));
+}
+
+pub fn end_replace(editor: &mut Editor) {
+    editor.replace = None;
+}
+
+pub fn replace_delete_backward(editor: &mut Editor) {
+    if let Some(replace) = &mut editor.replace {
+        match replace.stage {
+            ReplaceStage::Pattern => {
+                let mut pattern = replace.pattern.clone();
+                pattern.pop();
+                replace.set_pattern(pattern);
+            }
+            ReplaceStage::Replacement => {
+                replace.replacement.pop();
+            }
+            ReplaceStage::Confirm => return,
+        }
+    }
+    update_pending(editor);
+}
+
+pub fn replace_insert_character(editor: &mut Editor, c: char) {
+    if let Some(replace) = &mut editor.replace {
+        match replace.stage {
+            ReplaceStage::Pattern => {
+                let mut pattern = replace.pattern.clone();
+                pattern.push(c);
+                replace.set_pattern(pattern);
+            }
+            ReplaceStage::Replacement => replace.replacement.push(c),
+            ReplaceStage::Confirm => return,
+        }
+    }
+    update_pending(editor);
+}
+
+/// Moves from editing the pattern to editing the replacement.
+pub fn edit_replacement(editor: &mut Editor) {
+    if let Some(replace) = &mut editor.replace {
+        if replace.regex().is_some() {
+            replace.stage = ReplaceStage::Replacement;
+        }
+    }
+}
+
+/// Replaces the pending match, previewing the next.
+pub fn replace_one(editor: &mut Editor) {
+    replace_pending(editor);
+}
+
+/// Replaces every match in the buffer as a single revision and ends the replace.
+pub fn replace_all(editor: &mut Editor) {
+    let replace = match &editor.replace {
+        Some(replace) => replace,
+        None => return,
+    };
+
+    if let Some(regex) = replace.regex() {
+        let text_buffer = &mut editor.text_buffer;
+        let replacements = replace::find_all(text_buffer, regex, &replace.replacement, 0..text_buffer.length);
+        let offset = editor.cursor.selection(text_buffer).map_or(replace.origin, |s| s.start);
+        replace::apply(text_buffer, &replacements);
+
+        // Keep the cursor beside the text it was on, shifted by the replacements before it
+        let shift = replacements
+            .iter()
+            .filter(|r| r.range.end <= offset)
+            .map(|r| r.text.len() as isize - r.range.len() as isize)
+            .sum::<isize>();
+        editor.cursor.anchor = None;
+        editor.cursor.move_to_offset(text_buffer, (offset as isize + shift) as usize);
+    }
+    end_replace(editor);
+}
+
+/// Starts stepping through matches, confirming or skipping each.
+pub fn confirm_each(editor: &mut Editor) {
+    if let Some(replace) = &mut editor.replace {
+        if replace.regex().is_some() {
+            replace.stage = ReplaceStage::Confirm;
+        }
+    }
+    update_pending(editor);
+}
+
+/// Leaves the pending match as it is and moves on to the next.
+pub fn skip_one(editor: &mut Editor) {
+    if let Some(replace) = &mut editor.replace {
+        if let Some(pending) = &replace.pending {
+            replace.origin = match pending.range.is_empty() {
+                true => step_past(&editor.text_buffer, pending.range.end),
+                false => pending.range.end,
+            };
+        }
+    }
+    update_pending(editor);
+}
+
+/// Replaces the pending match and every match after it as a single revision, then ends the
+/// replace.
+pub fn replace_remaining(editor: &mut Editor) {
+    let replace = match &editor.replace {
+        Some(replace) => replace,
+        None => return,
+    };
+
+    if let (Some(regex), Some(pending)) = (replace.regex(), &replace.pending) {
+        let text_buffer = &mut editor.text_buffer;
+        let start = pending.range.start;
+        let replacements = replace::find_all(text_buffer, regex, &replace.replacement, 0..text_buffer.length)
+            .into_iter()
+            .filter(|r| r.range.start >= start)
+            .collect::<Vec<replace::Replacement>>();
+        replace::apply(text_buffer, &replacements);
+        editor.cursor.anchor = None;
+        editor.cursor.move_to_offset(text_buffer, start);
+    }
+    end_replace(editor);
+}
@@ -0,0 +1,109 @@
+use crate::editor::Editor;
+use crate::search::{self, Search};
+use std::ops::Range;
+
+/// Selects `found` with the cursor at its end, or collapses the cursor back to the search origin
+/// if there was no match.
+fn select_match(editor: &mut Editor, found: Option<Range<usize>>) {
+    let search = match &mut editor.search {
+        Some(search) => search,
+        None => return,
+    };
+
+    match &found {
+        Some(range) => {
+            editor.cursor.anchor = Some(range.start);
+            editor.cursor.move_to_offset(&editor.text_buffer, range.end);
+        }
+        None => {
+            editor.cursor.anchor = None;
+            editor.cursor.move_to_offset(&editor.text_buffer, search.origin);
+        }
+    }
+    search.current_match = found;
+}
+
+/// Jumps to the first match at or after the search origin.
+fn update_match(editor: &mut Editor) {
+    let found = match &editor.search {
+        Some(search) => search::find_next(
+            &editor.text_buffer,
+            &search.query,
+            search.case_sensitive,
+            search.origin,
+        ),
+        None => return,
+    };
+    select_match(editor, found);
+}
+
+pub fn start_search(editor: &mut Editor) {
+    editor.cursors.clear();
+    let origin = editor
+        .cursor
+        .selection(&editor.text_buffer)
+        .map(|s| s.start)
+        .unwrap_or_else(|| editor.cursor.offset(&editor.text_buffer));
+    editor.search = Some(Search::new(origin));
+}
+
+pub fn end_search(editor: &mut Editor) {
+    editor.search = None;
+}
+
+pub fn search_delete_backward(editor: &mut Editor) {
+    if let Some(search) = &mut editor.search {
+        search.query.pop();
+    }
+    update_match(editor);
+}
+
+pub fn search_insert_character(editor: &mut Editor, c: char) {
+    if let Some(search) = &mut editor.search {
+        search.query.push(c);
+    }
+    update_match(editor);
+}
+
+pub fn search_next(editor: &mut Editor) {
+    let found = match &mut editor.search {
+        Some(search) => {
+            let from = match &search.current_match {
+                Some(current_match) => current_match.end,
+                None => search.origin,
+            };
+            let found = search::find_next(&editor.text_buffer, &search.query, search.case_sensitive, from);
+            if let Some(found) = &found {
+                search.origin = found.start;
+            }
+            found
+        }
+        None => return,
+    };
+    select_match(editor, found);
+}
+
+pub fn search_previous(editor: &mut Editor) {
+    let found = match &mut editor.search {
+        Some(search) => {
+            let to = match &search.current_match {
+                Some(current_match) => current_match.start,
+                None => search.origin,
+            };
+            let found = search::find_previous(&editor.text_buffer, &search.query, search.case_sensitive, to);
+            if let Some(found) = &found {
+                search.origin = found.start;
+            }
+            found
+        }
+        None => return,
+    };
+    select_match(editor, found);
+}
+
+pub fn toggle_case_sensitivity(editor: &mut Editor) {
+    if let Some(search) = &mut editor.search {
+        search.case_sensitive = !search.case_sensitive;
+    }
+    update_match(editor);
+}
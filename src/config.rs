@@ -1,9 +1,43 @@
 pub struct EditorConfig {
     pub tab_width: u8,
-    pub indentation: IndentationPreference
+    pub indentation: IndentationPreference,
+    pub escape: EscapePolicy,
+    /// Whether tabs, trailing spaces and non-breaking spaces are drawn as visible glyphs.
+    pub show_whitespace: bool,
+    /// Whether lines wider than the window are wrapped onto several rows rather than scrolled.
+    pub soft_wrap: bool,
+    /// Characters other than letters and digits which word-wise movement treats as part of a word.
+    pub word_characters: String,
+    pub clipboard: ClipboardProvider,
 }
 
 pub enum IndentationPreference {
     Tabs,
     Spaces
-}
\ No newline at end of file
+}
+
+/// Which characters are shown escaped to angle bracket form rather than as themselves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscapePolicy {
+    /// Unicode is shown as is. Only what cannot be shown at all, such as control characters or
+    /// clusters taking up no columns, is escaped.
+    Native,
+    /// As `Native`, but characters which change how surrounding text appears without appearing
+    /// themselves, such as bidirectional overrides and zero width spaces, are also escaped.
+    Suspicious,
+    /// Everything outside of ASCII is escaped.
+    Ascii,
+}
+
+/// Where copied text is shared with other programs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClipboardProvider {
+    /// Copies stay within the editor's kill ring.
+    None,
+    /// Copies are written to the terminal as OSC 52 escape sequences, reaching the clipboard of
+    /// the machine the terminal runs on even over SSH. Pasting from it is not supported.
+    Osc52,
+    /// Copies are piped to the `copy` program and pastes are read from the output of the `paste`
+    /// program, each given as a program name followed by its arguments.
+    Command { copy: Vec<String>, paste: Vec<String> },
+}
@@ -1,7 +1,18 @@
+use crate::str_utils;
+use crate::text_buffer::TextBuffer;
+use std::ops::Range;
+
 pub struct Cursor {
     pub line: usize,
+    /// Index of the grapheme cluster the cursor sits before within its line.
     pub character: usize,
-    pub byte_offset: usize
+    pub byte_offset: usize,
+    /// Absolute byte offset of the fixed end of the selection. The cursor position is the
+    /// moving end (head) of the selection.
+    pub anchor: Option<usize>,
+    /// Display column vertical movement aims for, remembered across lines too short to reach it.
+    /// Cleared whenever the cursor moves other than vertically.
+    pub desired_column: Option<usize>,
 }
 
 impl Cursor {
@@ -9,7 +20,34 @@ impl Cursor {
         Cursor {
             line: 0,
             character: 0,
-            byte_offset: 0
+            byte_offset: 0,
+            anchor: None,
+            desired_column: None,
+        }
+    }
+
+    /// Moves the cursor to an absolute byte offset within the text buffer.
+    pub fn move_to_offset(&mut self, text_buffer: &dyn TextBuffer, offset: usize) {
+        self.desired_column = None;
+        self.line = text_buffer.line_index_at(offset);
+        let line = text_buffer.line_at(self.line);
+        self.byte_offset = std::cmp::min(offset - line.start_index, line.len());
+        self.character = str_utils::grapheme_count(&line.content[..self.byte_offset]);
+    }
+
+    /// Absolute byte offset of the cursor within the text buffer.
+    pub fn offset(&self, text_buffer: &dyn TextBuffer) -> usize {
+        text_buffer.line_at(self.line).start_index + self.byte_offset
+    }
+
+    /// Byte range spanned by the selection, if it is non-empty.
+    pub fn selection(&self, text_buffer: &dyn TextBuffer) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        let head = self.offset(text_buffer);
+        match anchor {
+            anchor if anchor < head => Some(anchor..head),
+            anchor if anchor > head => Some(head..anchor),
+            _ => None,
         }
     }
 }
@@ -1,90 +1,452 @@
-use crate::{config::{EditorConfig, IndentationPreference}, str_utils};
+use crate::{config::{ClipboardProvider, EditorConfig, EscapePolicy, IndentationPreference}, str_utils};
+use crate::backend::diff::DiffBackend;
+use crate::backend::terminal::TerminalBackend;
+use crate::clipboard::{self, Clipboard};
 use crate::commands;
 use crate::cursor::Cursor;
-use crate::file;
-use crate::renderer;
+use crate::event_loop::{EventLoop, Message, TimerId};
+use crate::file::{self, FileError, LineEnding};
+use crate::kill_ring::KillRing;
+use crate::renderer::{self, RenderContext};
+use crate::replace::{Replace, ReplaceStage};
+use crate::search::Search;
+use crate::status::{MessageKind, Status, StatusMessage};
 use crate::text_buffer::piece_table::PieceTable;
 use crate::text_buffer::TextBuffer;
 use crate::window::Window;
-use std::io::{stdout, Stdout, Write};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute, terminal,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+    event::{
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     Result,
 };
 
+const KILL_RING_CAPACITY: usize = 32;
+/// Longest time between two clicks at the same position for them to count as a double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a status message is shown for.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct Editor {
+    backend: DiffBackend<TerminalBackend>,
+    /// The clipboard shared with other programs.
+    pub clipboard: Box<dyn Clipboard>,
+    /// Whether exiting is waiting on the choice to save, discard or keep unsaved changes,
+    /// capturing key input until made.
+    pub confirming_exit: bool,
     pub config: EditorConfig,
+    /// Primary cursor, which the window follows.
     pub cursor: Cursor,
+    /// Additional cursors, edited alongside the primary cursor.
+    pub cursors: Vec<Cursor>,
+    /// Input, timers and background work waiting to be handled.
+    pub events: EventLoop,
     pub file_path: Option<PathBuf>,
+    /// Recently cut and copied text.
+    pub kill_ring: KillRing,
+    /// Time and terminal column and row of the last click, to recognise a double-click.
+    last_click: Option<(Instant, u16, u16)>,
+    pub line_ending: LineEnding,
+    /// Message shown in the status bar, and the timer which will clear it.
+    message: Option<(StatusMessage, TimerId)>,
+    /// Whether saving is refused, as the file could not be read or cannot be written.
+    pub read_only: bool,
+    /// Find and replace in progress, capturing key input while open.
+    pub replace: Option<Replace>,
     pub running: bool,
-    screen: Stdout,
+    /// Incremental search in progress, capturing key input while open.
+    pub search: Option<Search>,
     pub text_buffer: PieceTable,
     pub window: Window,
 }
 
 impl Editor {
     pub fn new(file_path: Option<PathBuf>) -> Self {
-        let file_contents = match &file_path {
-            Some(path) => file::load(&path).unwrap_or(String::new()),
-            _ => String::new(),
+        let loaded = file_path.as_ref().map(|path| (path, file::load(path)));
+        // A file which failed to load is opened read-only, so that it is not overwritten by an
+        // empty buffer standing in for its contents
+        let (file_contents, read_only, load_error) = match loaded {
+            Some((path, Ok(contents))) => (contents, !file::is_writable(path), None),
+            Some((_, Err(FileError::NotFound))) | None => (String::new(), false, None),
+            Some((path, Err(e))) => {
+                let error = format!("Could not open {}: {}", path.display(), e);
+                (String::new(), true, Some(error))
+            }
         };
 
+        let line_ending = LineEnding::detect(&file_contents);
         let text_buffer = PieceTable::new(file_contents);
         let config = EditorConfig {
             tab_width: 4,
             indentation: IndentationPreference::Tabs,
+            escape: EscapePolicy::Suspicious,
+            show_whitespace: false,
+            soft_wrap: false,
+            word_characters: String::from("_"),
+            clipboard: ClipboardProvider::Osc52,
         };
+        let clipboard = clipboard::from_config(&config.clipboard);
         let cursor = Cursor::new();
         let window = Window::new(0, 0, 0, 0);
 
-        Self {
+        let mut editor = Self {
+            backend: DiffBackend::new(TerminalBackend::new()),
+            clipboard,
+            confirming_exit: false,
             config,
             cursor,
+            cursors: Vec::new(),
+            events: EventLoop::new(),
             file_path,
+            kill_ring: KillRing::new(KILL_RING_CAPACITY),
+            last_click: None,
+            line_ending,
+            message: None,
+            read_only,
+            replace: None,
             running: false,
-            screen: stdout(),
+            search: None,
             text_buffer,
             window,
+        };
+        match load_error {
+            Some(error) => editor.show_message(MessageKind::Error, error),
+            None if read_only => editor.show_message(MessageKind::Info, String::from("Opened read-only")),
+            None => (),
         }
+        editor
     }
 
     pub fn start(&mut self) {
         self.running = true;
 
-        execute!(self.screen, EnterAlternateScreen);
-        terminal::enable_raw_mode();
+        self.backend.inner_mut().enter();
+        self.events.listen_for_input();
 
         while self.running {
-            renderer::render(
-                &mut self.screen,
-                &mut self.text_buffer,
-                &mut self.cursor,
-                &mut self.window,
-                &self.config,
-            );
-
-            if let Ok(Event::Key(event)) = event::read() {
-                self.handle_key_event(event);
+            let file_name = self
+                .file_path
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy());
+            let context = RenderContext {
+                text_buffer: &self.text_buffer,
+                cursor: &self.cursor,
+                secondary_cursors: &self.cursors,
+                editor_config: &self.config,
+                search: self.search.as_ref(),
+                replace: self.replace.as_ref(),
+                status: Status {
+                    file_name: file_name.as_deref(),
+                    modified: self.text_buffer.is_modified(),
+                    read_only: self.read_only,
+                    confirming_exit: self.confirming_exit,
+                    line_ending: self.line_ending,
+                    message: self.message.as_ref().map(|(message, _)| message),
+                },
+            };
+            renderer::render(&mut self.backend, &mut self.window, &context);
+
+            // Everything that arrives together is handled before redrawing once, unless one of
+            // the messages exits the editor
+            for message in self.events.wait() {
+                if !self.running {
+                    break;
+                }
+                match message {
+                    Message::Input(event) => self.handle_event(event),
+                    Message::Task(task) => task(self),
+                }
+            }
+            for callback in self.events.take_due_timers() {
+                callback(self);
+            }
+        }
+
+        self.events.stop_listening();
+        self.backend.inner_mut().leave();
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(event) if event.kind == KeyEventKind::Press => self.handle_key_event(event),
+            Event::Mouse(event) => self.handle_mouse_event(event),
+            Event::Paste(text) => self.handle_paste(text),
+            Event::Resize(columns, rows) => self.handle_resize(columns, rows),
+            _ => (),
+        }
+    }
+
+    /// Message shown in the status bar, if any.
+    pub fn message(&self) -> Option<&StatusMessage> {
+        self.message.as_ref().map(|(message, _)| message)
+    }
+
+    /// Shows `text` in the status bar until it times out or another message replaces it.
+    pub fn show_message(&mut self, kind: MessageKind, text: String) {
+        if let Some((_, timer)) = self.message.take() {
+            self.events.cancel_timer(timer);
+        }
+        let timer = self.events.set_timer(MESSAGE_TIMEOUT, |editor| editor.message = None);
+        self.message = Some((StatusMessage { kind, text }, timer));
+    }
+
+    /// Runs `command` once for each cursor, in document order, as a single revision.
+    /// Each cursor is made the primary `cursor` while its command runs, and the cursors after it
+    /// are shifted by however much the command grew or shrank the text buffer. Cursors left at the
+    /// same position afterwards are merged.
+    ///
+    /// The history only records text, so undoing or redoing the revision returns to a single
+    /// cursor at the edit, dropping the secondary cursors.
+    pub fn for_each_cursor(&mut self, command: impl Fn(&mut Editor)) {
+        if self.cursors.is_empty() {
+            command(self);
+            return;
+        }
+
+        let primary = std::mem::replace(&mut self.cursor, Cursor::new());
+        let text_buffer = &self.text_buffer;
+        let mut positions = std::mem::take(&mut self.cursors)
+            .into_iter()
+            .map(|c| (false, c))
+            .chain(std::iter::once((true, primary)))
+            .map(|(is_primary, c)| (is_primary, c.offset(text_buffer), c.anchor, c.desired_column))
+            .collect::<Vec<(bool, usize, Option<usize>, Option<usize>)>>();
+        positions.sort_by_key(|(_, offset, _, _)| *offset);
+
+        let shift = |offset: usize, delta: isize| std::cmp::max(offset as isize + delta, 0) as usize;
+        let mut cursors = Vec::new();
+        self.text_buffer.begin_revision();
+        for i in 0..positions.len() {
+            let (is_primary, offset, anchor, desired_column) = positions[i];
+            self.cursor.move_to_offset(&self.text_buffer, offset);
+            self.cursor.anchor = anchor;
+            self.cursor.desired_column = desired_column;
+
+            let length = self.text_buffer.length;
+            command(self);
+            let delta = self.text_buffer.length as isize - length as isize;
+            for (_, offset, anchor, _) in positions[i + 1..].iter_mut() {
+                *offset = shift(*offset, delta);
+                *anchor = anchor.map(|a| shift(a, delta));
+            }
+
+            cursors.push((is_primary, std::mem::replace(&mut self.cursor, Cursor::new())));
+        }
+        self.text_buffer.end_revision();
+
+        for (is_primary, mut cursor) in cursors {
+            // An edit at a later cursor can remove the end of an earlier cursor's line, as
+            // deleting the last line does with the line break before it
+            let line_index = std::cmp::min(cursor.line, self.text_buffer.line_count() - 1);
+            let line = self.text_buffer.line_at(line_index);
+            if cursor.line != line_index || cursor.byte_offset > line.len() {
+                let byte_offset = std::cmp::min(cursor.byte_offset, line.len());
+                cursor.move_to_offset(&self.text_buffer, line.start_index + byte_offset);
+            }
+            match is_primary {
+                true => self.cursor = cursor,
+                false => self.cursors.push(cursor),
             }
         }
+        self.merge_cursors();
+    }
+
+    /// Removes secondary cursors that share a position with the primary cursor or each other.
+    fn merge_cursors(&mut self) {
+        let mut positions = vec![(self.cursor.line, self.cursor.byte_offset)];
+        self.cursors.retain(|c| {
+            let position = (c.line, c.byte_offset);
+            let is_unique = !positions.contains(&position);
+            positions.push(position);
+            is_unique
+        });
+    }
+
+    /// Lays the screen out again for the terminal's new size, scrolling to keep the cursor in view.
+    fn handle_resize(&mut self, columns: u16, rows: u16) {
+        self.backend.inner_mut().resize(columns, rows);
+        self.window.follow_cursor = true;
+    }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        let (column, row) = (mouse_event.column, mouse_event.row);
+        let is_prompt_open = self.confirming_exit || self.search.is_some() || self.replace.is_some();
+        match mouse_event.kind {
+            MouseEventKind::ScrollDown => commands::mouse::scroll_down(self),
+            MouseEventKind::ScrollUp => commands::mouse::scroll_up(self),
+            // Clicks on the status bar, or while a prompt is capturing input, are ignored
+            _ if is_prompt_open || row >= self.window.height => (),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let is_double_click = match self.last_click {
+                    Some((time, c, r)) => (c, r) == (column, row) && time.elapsed() < DOUBLE_CLICK_INTERVAL,
+                    None => false,
+                };
+                match is_double_click {
+                    true => {
+                        commands::mouse::select_word(self, column, row);
+                        self.last_click = None;
+                    }
+                    false => {
+                        commands::mouse::click(self, column, row);
+                        self.last_click = Some((Instant::now(), column, row));
+                    }
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => commands::mouse::drag(self, column, row),
+            _ => (),
+        }
+    }
+
+    /// Inserts text pasted into the terminal as one edit at each cursor, rather than as a key
+    /// event per character. Line breaks are converted to the buffer's line ending.
+    fn handle_paste(&mut self, text: String) {
+        // Terminals send pasted line breaks as carriage returns
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        // Prompts are a single line, so only take the first line of the paste
+        let prompt_text = text.lines().next().unwrap_or("");
+        if self.confirming_exit {
+            return;
+        }
+        if self.search.is_some() {
+            prompt_text.chars().for_each(|c| commands::search::search_insert_character(self, c));
+            return;
+        }
+        if self.replace.is_some() {
+            prompt_text.chars().for_each(|c| commands::replace::replace_insert_character(self, c));
+            return;
+        }
+
+        let text = match self.line_ending {
+            LineEnding::Lf => text,
+            line_ending => text.replace('\n', line_ending.as_str()),
+        };
+        self.window.follow_cursor = true;
+        self.for_each_cursor(|e| commands::edit::insert_text(e, &text));
+    }
+
+    fn handle_confirm_exit_key_event(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Char('s') => commands::app::save_and_exit(self),
+            KeyCode::Char('n') | KeyCode::Char('d') => commands::app::discard_and_exit(self),
+            KeyCode::Char('c') | KeyCode::Esc => commands::app::cancel_exit(self),
+            _ => (),
+        }
+    }
+
+    fn handle_search_key_event(&mut self, key_event: KeyEvent) {
+        match (key_event.code, key_event.modifiers) {
+            (KeyCode::Char('q'), KeyModifiers::CONTROL) => commands::app::exit(self),
+            (KeyCode::Char('c'), KeyModifiers::ALT) => commands::search::toggle_case_sensitivity(self),
+            (KeyCode::Char(c), m) if (m - KeyModifiers::SHIFT).is_empty() => commands::search::search_insert_character(self, c),
+            (KeyCode::Backspace, _) => commands::search::search_delete_backward(self),
+            (KeyCode::Enter, _) => commands::search::search_next(self),
+            (KeyCode::Down, _) => commands::search::search_next(self),
+            (KeyCode::Up, _) => commands::search::search_previous(self),
+            (KeyCode::Esc, _) => commands::search::end_search(self),
+            _ => ()
+        };
+    }
+
+    fn handle_replace_key_event(&mut self, key_event: KeyEvent) {
+        let stage = match &self.replace {
+            Some(replace) => replace.stage,
+            None => return,
+        };
+
+        match (stage, key_event.code, key_event.modifiers) {
+            (_, KeyCode::Char('q'), KeyModifiers::CONTROL) => commands::app::exit(self),
+            (_, KeyCode::Esc, _) => commands::replace::end_replace(self),
+            (ReplaceStage::Confirm, KeyCode::Char('y'), _) => commands::replace::replace_one(self),
+            (ReplaceStage::Confirm, KeyCode::Char('n'), _) => commands::replace::skip_one(self),
+            (ReplaceStage::Confirm, KeyCode::Char('a'), _) => commands::replace::replace_remaining(self),
+            (ReplaceStage::Confirm, KeyCode::Char('q'), _) => commands::replace::end_replace(self),
+            (ReplaceStage::Confirm, _, _) => (),
+            (ReplaceStage::Replacement, KeyCode::Char('a'), KeyModifiers::ALT) => commands::replace::replace_all(self),
+            (ReplaceStage::Replacement, KeyCode::Char('c'), KeyModifiers::ALT) => commands::replace::confirm_each(self),
+            (ReplaceStage::Replacement, KeyCode::Enter, _) => commands::replace::replace_one(self),
+            (ReplaceStage::Pattern, KeyCode::Enter, _) => commands::replace::edit_replacement(self),
+            (_, KeyCode::Char(c), m) if (m - KeyModifiers::SHIFT).is_empty() => commands::replace::replace_insert_character(self, c),
+            (_, KeyCode::Backspace, _) => commands::replace::replace_delete_backward(self),
+            _ => ()
+        };
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        self.window.follow_cursor = true;
+        if self.confirming_exit {
+            self.handle_confirm_exit_key_event(key_event);
+            return;
+        }
+        if self.search.is_some() {
+            self.handle_search_key_event(key_event);
+            return;
+        }
+        if self.replace.is_some() {
+            self.handle_replace_key_event(key_event);
+            return;
+        }
+
+        let control_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        let control_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
         match (key_event.code, key_event.modifiers) {
             (KeyCode::Char('q'), KeyModifiers::CONTROL) => commands::app::exit(self),
             (KeyCode::Char('s'), KeyModifiers::CONTROL) => commands::app::save(self),
-            (KeyCode::Char(c), _) => commands::edit::insert_character(self, c),
-            (KeyCode::Backspace, _) => commands::edit::delete_backward(self),
-            (KeyCode::Enter, _) => commands::edit::insert_newline(self),
-            (KeyCode::Tab, _) => commands::edit::insert_tab(self),
-            (KeyCode::Left, _) => commands::cursor::cursor_backward(self),
-            (KeyCode::Right, _) => commands::cursor::cursor_forward(self),
-            (KeyCode::Up, _) => commands::cursor::cursor_up(self),
-            (KeyCode::Down, _) => commands::cursor::cursor_down(self),
+            (KeyCode::Char('z'), KeyModifiers::CONTROL) => commands::edit::undo(self),
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => commands::edit::redo(self),
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => commands::search::start_search(self),
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => commands::replace::start_replace(self),
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => commands::cursor::add_cursor_at_next_occurrence(self),
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => self.for_each_cursor(commands::cursor::cursor_matching_bracket),
+            (KeyCode::Char('w'), KeyModifiers::ALT) => commands::app::toggle_whitespace(self),
+            (KeyCode::Char('z'), KeyModifiers::ALT) => commands::app::toggle_soft_wrap(self),
+            (KeyCode::Up, m) if m == control_alt => commands::cursor::add_cursor_above(self),
+            (KeyCode::Down, m) if m == control_alt => commands::cursor::add_cursor_below(self),
+            (KeyCode::Esc, _) => commands::cursor::remove_secondary_cursors(self),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => commands::clipboard::copy(self),
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => commands::clipboard::cut(self),
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => commands::clipboard::paste(self),
+            (KeyCode::Char('y'), KeyModifiers::ALT) => commands::clipboard::paste_previous(self),
+            (KeyCode::Char('h'), KeyModifiers::CONTROL) => self.for_each_cursor(commands::edit::delete_word_backward),
+            (KeyCode::Char('k'), KeyModifiers::CONTROL) => self.for_each_cursor(commands::edit::delete_to_line_end),
+            (KeyCode::Char('k'), KeyModifiers::ALT) => commands::edit::delete_lines(self),
+            (KeyCode::Char(c), _) => self.for_each_cursor(|e| commands::edit::insert_character(e, c)),
+            (KeyCode::Backspace, KeyModifiers::CONTROL) => self.for_each_cursor(commands::edit::delete_word_backward),
+            (KeyCode::Backspace, KeyModifiers::ALT) => self.for_each_cursor(commands::edit::delete_word_backward),
+            (KeyCode::Delete, KeyModifiers::CONTROL) => self.for_each_cursor(commands::edit::delete_word_forward),
+            (KeyCode::Delete, _) => self.for_each_cursor(commands::edit::delete_forward),
+            (KeyCode::Backspace, _) => self.for_each_cursor(commands::edit::delete_backward),
+            (KeyCode::Enter, _) => self.for_each_cursor(commands::edit::insert_newline),
+            (KeyCode::Tab, _) => self.for_each_cursor(commands::edit::insert_tab),
+            (KeyCode::Left, m) if m == control_shift => self.for_each_cursor(commands::cursor::select_word_backward),
+            (KeyCode::Right, m) if m == control_shift => self.for_each_cursor(commands::cursor::select_word_forward),
+            (KeyCode::Home, m) if m == control_shift => self.for_each_cursor(commands::cursor::select_document_start),
+            (KeyCode::End, m) if m == control_shift => self.for_each_cursor(commands::cursor::select_document_end),
+            (KeyCode::Left, KeyModifiers::CONTROL) => self.for_each_cursor(commands::cursor::cursor_word_backward),
+            (KeyCode::Right, KeyModifiers::CONTROL) => self.for_each_cursor(commands::cursor::cursor_word_forward),
+            (KeyCode::Home, KeyModifiers::CONTROL) => self.for_each_cursor(commands::cursor::cursor_document_start),
+            (KeyCode::End, KeyModifiers::CONTROL) => self.for_each_cursor(commands::cursor::cursor_document_end),
+            (KeyCode::Home, KeyModifiers::SHIFT) => self.for_each_cursor(commands::cursor::select_line_start),
+            (KeyCode::End, KeyModifiers::SHIFT) => self.for_each_cursor(commands::cursor::select_line_end),
+            (KeyCode::PageUp, KeyModifiers::SHIFT) => commands::cursor::select_page_up(self),
+            (KeyCode::PageDown, KeyModifiers::SHIFT) => commands::cursor::select_page_down(self),
+            (KeyCode::Left, KeyModifiers::SHIFT) => self.for_each_cursor(commands::cursor::select_backward),
+            (KeyCode::Right, KeyModifiers::SHIFT) => self.for_each_cursor(commands::cursor::select_forward),
+            (KeyCode::Up, KeyModifiers::SHIFT) => self.for_each_cursor(commands::cursor::select_up),
+            (KeyCode::Down, KeyModifiers::SHIFT) => self.for_each_cursor(commands::cursor::select_down),
+            (KeyCode::Left, _) => self.for_each_cursor(commands::cursor::cursor_backward),
+            (KeyCode::Right, _) => self.for_each_cursor(commands::cursor::cursor_forward),
+            (KeyCode::Up, _) => self.for_each_cursor(commands::cursor::cursor_up),
+            (KeyCode::Down, _) => self.for_each_cursor(commands::cursor::cursor_down),
+            (KeyCode::Home, _) => self.for_each_cursor(commands::cursor::cursor_line_start),
+            (KeyCode::End, _) => self.for_each_cursor(commands::cursor::cursor_line_end),
+            (KeyCode::PageUp, _) => commands::cursor::cursor_page_up(self),
+            (KeyCode::PageDown, _) => commands::cursor::cursor_page_down(self),
             _ => ()
         };
     }
@@ -92,7 +454,170 @@ impl Editor {
 
 impl Drop for Editor {
     fn drop(&mut self) {
-        execute!(self.screen, LeaveAlternateScreen);
-        terminal::disable_raw_mode();
+        self.events.stop_listening();
+        self.backend.inner_mut().leave();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::clipboard::null::NullClipboard;
+
+    /// An editor holding `text`, with the primary cursor at the first of `offsets` and a secondary
+    /// cursor at each of the rest.
+    pub fn editor_with(text: &str, offsets: &[usize]) -> Editor {
+        let mut editor = Editor::new(None);
+        editor.clipboard = Box::new(NullClipboard);
+        editor.text_buffer = PieceTable::new(String::from(text));
+        editor.cursor.move_to_offset(&editor.text_buffer, offsets[0]);
+        for offset in &offsets[1..] {
+            let mut cursor = Cursor::new();
+            cursor.move_to_offset(&editor.text_buffer, *offset);
+            editor.cursors.push(cursor);
+        }
+        editor
+    }
+
+    /// Offset of the primary cursor, followed by those of the secondary cursors in order.
+    pub fn cursor_offsets(editor: &Editor) -> Vec<usize> {
+        std::iter::once(&editor.cursor)
+            .chain(editor.cursors.iter())
+            .map(|c| c.offset(&editor.text_buffer))
+            .collect()
+    }
+
+    #[test]
+    fn paste_at_every_cursor_as_one_revision() {
+        let editor = &mut editor_with("ab\ncd", &[4, 1]);
+        editor.handle_paste(String::from("x\ry\r\nz"));
+
+        assert_eq!("ax\ny\nzb\ncx\ny\nzd", editor.text_buffer.all_content());
+        assert_eq!(vec![14, 6], cursor_offsets(editor));
+        commands::edit::undo(editor);
+        assert_eq!("ab\ncd", editor.text_buffer.all_content());
+    }
+
+    #[test]
+    fn paste_uses_buffer_line_ending() {
+        let editor = &mut editor_with("ab\r\ncd", &[0]);
+        editor.line_ending = LineEnding::Crlf;
+        editor.handle_paste(String::from("x\ry\nz"));
+
+        assert_eq!("x\r\ny\r\nzab\r\ncd", editor.text_buffer.all_content());
+    }
+
+    fn left_mouse(kind: fn(MouseButton) -> MouseEventKind, column: u16, row: u16) -> Event {
+        Event::Mouse(MouseEvent {
+            kind: kind(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn second_click_in_place_selects_word() {
+        let editor = &mut editor_with("let foo_bar = 1;\nx", &[0]);
+        editor.window.width = 20;
+        editor.window.height = 5;
+
+        editor.handle_event(left_mouse(MouseEventKind::Down, 6, 0));
+        assert_eq!(None, editor.cursor.selection(&editor.text_buffer));
+        editor.handle_event(left_mouse(MouseEventKind::Down, 6, 0));
+        assert_eq!(Some(4..11), editor.cursor.selection(&editor.text_buffer));
+
+        // A third click starts over, as does a second click somewhere else
+        editor.handle_event(left_mouse(MouseEventKind::Down, 6, 0));
+        assert_eq!(None, editor.cursor.selection(&editor.text_buffer));
+        editor.handle_event(left_mouse(MouseEventKind::Down, 1, 0));
+        assert_eq!(None, editor.cursor.selection(&editor.text_buffer));
+
+        editor.handle_event(left_mouse(MouseEventKind::Drag, 1, 1));
+        assert_eq!(Some(1..18), editor.cursor.selection(&editor.text_buffer));
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn exit_prompt_captures_keys_until_answered() {
+        let editor = &mut editor_with("ab", &[2]);
+        editor.running = true;
+        editor.handle_event(key(KeyCode::Char('c'), KeyModifiers::NONE));
+        editor.handle_event(key(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert!(editor.confirming_exit);
+
+        // Other keys are ignored rather than edited into the buffer
+        editor.handle_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!("abc", editor.text_buffer.all_content());
+        assert!(!editor.confirming_exit);
+        assert!(editor.running);
+
+        editor.handle_event(key(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        editor.handle_event(key(KeyCode::Char('n'), KeyModifiers::NONE));
+        assert!(!editor.running);
+    }
+
+    #[test]
+    fn insert_at_cursors_on_different_lines() {
+        let editor = &mut editor_with("ab\ncd\nef", &[4, 1, 7]);
+        editor.for_each_cursor(|e| commands::edit::insert_character(e, 'x'));
+
+        assert_eq!("axb\ncxd\nexf", editor.text_buffer.all_content());
+        assert_eq!(vec![6, 2, 10], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn insert_shifts_later_cursors_on_same_line() {
+        let editor = &mut editor_with("abc", &[1, 0, 3]);
+        editor.for_each_cursor(|e| commands::edit::insert_text(e, "xy"));
+
+        assert_eq!("xyaxybcxy", editor.text_buffer.all_content());
+        assert_eq!(vec![5, 2, 9], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn backspace_shifts_later_cursors_on_same_line() {
+        let editor = &mut editor_with("abcd", &[4, 2]);
+        editor.for_each_cursor(commands::edit::delete_backward);
+
+        assert_eq!("ac", editor.text_buffer.all_content());
+        assert_eq!(vec![2, 1], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn backspace_merges_cursors_that_meet() {
+        let editor = &mut editor_with("ab\ncd", &[1, 2]);
+        editor.for_each_cursor(commands::edit::delete_backward);
+
+        assert_eq!("\ncd", editor.text_buffer.all_content());
+        assert_eq!(vec![0], cursor_offsets(editor));
+
+        // Joining lines brings cursors at line starts back onto the line before
+        let editor = &mut editor_with("a\nb\nc", &[2, 3, 4]);
+        editor.for_each_cursor(commands::edit::delete_backward);
+
+        assert_eq!("ac", editor.text_buffer.all_content());
+        assert_eq!(vec![1], cursor_offsets(editor));
+    }
+
+    #[test]
+    fn edit_at_every_cursor_undone_as_one_revision() {
+        let editor = &mut editor_with("ab\ncd\nef", &[4, 1, 7]);
+        editor.for_each_cursor(|e| commands::edit::insert_character(e, 'x'));
+        editor.for_each_cursor(|e| commands::edit::insert_character(e, 'y'));
+
+        assert_eq!("axyb\ncxyd\nexyf", editor.text_buffer.all_content());
+        commands::edit::undo(editor);
+        assert_eq!("axb\ncxd\nexf", editor.text_buffer.all_content());
+        assert!(editor.cursors.is_empty());
+        commands::edit::undo(editor);
+        assert_eq!("ab\ncd\nef", editor.text_buffer.all_content());
+        commands::edit::redo(editor);
+        assert_eq!("axb\ncxd\nexf", editor.text_buffer.all_content());
+        assert!(editor.cursors.is_empty());
     }
 }
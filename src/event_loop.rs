@@ -0,0 +1,250 @@
+use crate::editor::Editor;
+use crossterm::event::{self, Event};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Longest the input thread waits for input before checking whether it has been stopped.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Work to be run against the editor on its own thread.
+pub type Callback = Box<dyn FnOnce(&mut Editor)>;
+
+/// Something for the editor to handle, sent to the event loop from any thread.
+pub enum Message {
+    Input(Event),
+    Task(Box<dyn FnOnce(&mut Editor) + Send>),
+}
+
+/// Identifies a timer, so that it can be cancelled before it is due.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimerId(u64);
+
+/// Values, such as callbacks, waiting for their deadlines to pass.
+pub struct Timers<T> {
+    timers: Vec<(Instant, TimerId, T)>,
+    next_id: u64,
+}
+
+impl<T> Timers<T> {
+    pub fn new() -> Timers<T> {
+        Timers {
+            timers: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn add(&mut self, deadline: Instant, value: T) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push((deadline, id, value));
+        id
+    }
+
+    /// Removes the timer `id`, returning whether it had yet to be taken.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let count = self.timers.len();
+        self.timers.retain(|(_, timer_id, _)| *timer_id != id);
+        self.timers.len() < count
+    }
+
+    /// Earliest deadline of any timer.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|(deadline, _, _)| *deadline).min()
+    }
+
+    /// Removes and returns the values of timers due by `now`, earliest first.
+    pub fn take_due(&mut self, now: Instant) -> Vec<T> {
+        let (mut due, pending) = std::mem::take(&mut self.timers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(deadline, _, _)| *deadline <= now);
+        self.timers = pending;
+        due.sort_by_key(|(deadline, id, _)| (*deadline, id.0));
+        due.into_iter().map(|(_, _, value)| value).collect()
+    }
+}
+
+impl<T> Default for Timers<T> {
+    fn default() -> Timers<T> {
+        Timers::new()
+    }
+}
+
+/// Multiplexes terminal input, messages from worker threads and timers, so that the editor can
+/// act between keystrokes without blocking on any one of them.
+pub struct EventLoop {
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    timers: Timers<Callback>,
+    /// Thread started by `listen_for_input`, and the flag which asks it to stop.
+    input: Option<(JoinHandle<()>, Arc<AtomicBool>)>,
+}
+
+impl EventLoop {
+    pub fn new() -> EventLoop {
+        let (sender, receiver) = mpsc::channel();
+        EventLoop {
+            sender,
+            receiver,
+            timers: Timers::new(),
+            input: None,
+        }
+    }
+
+    /// Starts forwarding terminal input to the loop from a thread of its own, until
+    /// `stop_listening` is called.
+    pub fn listen_for_input(&mut self) {
+        let sender = self.sender.clone();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&stopped);
+        // Polling rather than blocking on a read lets the thread notice it has been stopped
+        let thread = thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match event::poll(INPUT_POLL_INTERVAL) {
+                    Ok(true) => (),
+                    Ok(false) => continue,
+                    Err(_) => break,
+                }
+                let sent = match event::read() {
+                    Ok(event) => sender.send(Message::Input(event)).is_ok(),
+                    Err(_) => false,
+                };
+                if !sent {
+                    break;
+                }
+            }
+        });
+        self.input = Some((thread, stopped));
+    }
+
+    /// Stops the thread forwarding terminal input, waiting for it to finish so that it reads no
+    /// input meant for whatever runs after the editor.
+    pub fn stop_listening(&mut self) {
+        if let Some((thread, stopped)) = self.input.take() {
+            stopped.store(true, Ordering::Relaxed);
+            let _ = thread.join();
+        }
+    }
+
+    /// A sender through which other threads can post messages to the loop.
+    pub fn sender(&self) -> Sender<Message> {
+        self.sender.clone()
+    }
+
+    /// Runs `work` on a worker thread, then `then` with its result on the editor's thread.
+    pub fn spawn<T: Send + 'static>(
+        &self,
+        work: impl FnOnce() -> T + Send + 'static,
+        then: impl FnOnce(&mut Editor, T) + Send + 'static,
+    ) {
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let result = work();
+            let _ = sender.send(Message::Task(Box::new(move |editor| then(editor, result))));
+        });
+    }
+
+    /// Runs `callback` on the editor's thread once `delay` has passed.
+    pub fn set_timer(
+        &mut self,
+        delay: Duration,
+        callback: impl FnOnce(&mut Editor) + 'static,
+    ) -> TimerId {
+        self.timers.add(Instant::now() + delay, Box::new(callback))
+    }
+
+    pub fn cancel_timer(&mut self, id: TimerId) -> bool {
+        self.timers.cancel(id)
+    }
+
+    /// Blocks until a message arrives or a timer is due, then returns every message waiting, so
+    /// that a burst of them can be handled before the screen is next redrawn. Returns no messages
+    /// if woken by a timer.
+    pub fn wait(&mut self) -> Vec<Message> {
+        let first = match self.timers.next_deadline() {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match self.receiver.recv_timeout(timeout) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                        return Vec::new()
+                    }
+                }
+            }
+            None => match self.receiver.recv() {
+                Ok(message) => message,
+                Err(_) => return Vec::new(),
+            },
+        };
+
+        std::iter::once(first)
+            .chain(self.receiver.try_iter())
+            .collect()
+    }
+
+    /// Removes and returns the callbacks of timers which are due.
+    pub fn take_due_timers(&mut self) -> Vec<Callback> {
+        self.timers.take_due(Instant::now())
+    }
+}
+
+impl Default for EventLoop {
+    fn default() -> EventLoop {
+        EventLoop::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timers_taken_when_due_in_deadline_order() {
+        let now = Instant::now();
+        let timers = &mut Timers::new();
+        timers.add(now + Duration::from_secs(2), "later");
+        let cancelled = timers.add(now, "cancelled");
+        timers.add(now, "first");
+        timers.add(now - Duration::from_secs(1), "overdue");
+
+        assert!(timers.cancel(cancelled));
+        assert!(!timers.cancel(cancelled));
+        assert_eq!(Some(now - Duration::from_secs(1)), timers.next_deadline());
+        assert_eq!(vec!["overdue", "first"], timers.take_due(now));
+        assert_eq!(Some(now + Duration::from_secs(2)), timers.next_deadline());
+        assert_eq!(Vec::<&str>::new(), timers.take_due(now));
+    }
+
+    #[test]
+    fn wait_returns_worker_results_and_wakes_for_timers() {
+        let event_loop = &mut EventLoop::new();
+        event_loop.spawn(|| 1 + 1, |_, _| ());
+        event_loop.spawn(|| 2 + 2, |_, _| ());
+
+        let mut tasks = 0;
+        while tasks < 2 {
+            tasks += event_loop
+                .wait()
+                .iter()
+                .filter(|m| matches!(m, Message::Task(_)))
+                .count();
+        }
+
+        event_loop.set_timer(Duration::from_millis(10), |_| ());
+        assert!(event_loop.wait().is_empty());
+        assert_eq!(1, event_loop.take_due_timers().len());
+    }
+
+    #[test]
+    fn stop_listening_ends_input_thread() {
+        let event_loop = &mut EventLoop::new();
+        event_loop.listen_for_input();
+        event_loop.stop_listening();
+        assert!(event_loop.input.is_none());
+
+        // Stopping again, or without having listened, does nothing
+        event_loop.stop_listening();
+    }
+}
@@ -1,26 +1,134 @@
-use std::fs::File;
+use std::fmt;
+use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::prelude::{Read, Write};
 use std::path::Path;
 
-pub fn load(path: &Path) -> io::Result<String> {
-    let file_contents = match File::open(path) {
-        Ok(mut f) => {
-            let mut contents = String::new();
-            f.read_to_string(&mut contents)?;
-            contents
+/// Line break convention of a file, judged by its first line break.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn detect(content: &str) -> LineEnding {
+        match content.find('\n') {
+            Some(i) if content[..i].ends_with('\r') => LineEnding::Crlf,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    /// The line break itself.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+}
+
+/// Why a file could not be loaded or saved.
+#[derive(Debug)]
+pub enum FileError {
+    NotFound,
+    PermissionDenied,
+    IsDirectory,
+    InvalidUtf8,
+    Other(io::Error),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::NotFound => write!(f, "file not found"),
+            FileError::PermissionDenied => write!(f, "permission denied"),
+            FileError::IsDirectory => write!(f, "is a directory"),
+            FileError::InvalidUtf8 => write!(f, "not valid UTF-8"),
+            FileError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+impl From<io::Error> for FileError {
+    fn from(e: io::Error) -> FileError {
+        match e.kind() {
+            io::ErrorKind::NotFound => FileError::NotFound,
+            io::ErrorKind::PermissionDenied => FileError::PermissionDenied,
+            io::ErrorKind::InvalidData => FileError::InvalidUtf8,
+            _ => FileError::Other(e),
         }
-        _ => String::new(),
-    };
+    }
+}
+
+pub fn load(path: &Path) -> Result<String, FileError> {
+    if path.is_dir() {
+        return Err(FileError::IsDirectory);
+    }
+
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+pub fn save(path: &Path, content: String) -> Result<(), FileError> {
+    if path.is_dir() {
+        return Err(FileError::IsDirectory);
+    }
+
+    File::create(path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
 
-    Ok(file_contents)
+/// Whether `path` can be written to, opening it without truncating. A file which does not exist
+/// yet is taken to be writable, leaving any failure to create it to be reported on saving.
+pub fn is_writable(path: &Path) -> bool {
+    match OpenOptions::new().write(true).open(path) {
+        Ok(_) => true,
+        Err(e) => e.kind() == io::ErrorKind::NotFound,
+    }
 }
 
-pub fn save(path: &Path, content: String) -> io::Result<()> {
-    let file = &mut File::create(path)?;
-    Ok(for c in content.chars() {
-        let buf = &mut [0u8; 4];
-        let subslice = c.encode_utf8(buf);
-        file.write(subslice.as_bytes())?;
-    })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rstext-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn save_then_load() {
+        let path = &temp_path("save_then_load");
+        save(path, String::from("fn main() {}\n")).unwrap();
+
+        assert_eq!("fn main() {}\n", load(path).unwrap());
+        assert!(is_writable(path));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_errors_are_typed() {
+        let path = &temp_path("load_errors_are_typed");
+        assert!(matches!(load(path), Err(FileError::NotFound)));
+        assert!(is_writable(path));
+
+        std::fs::write(path, [b'a', 0xff, b'b']).unwrap();
+        assert!(matches!(load(path), Err(FileError::InvalidUtf8)));
+        std::fs::remove_file(path).unwrap();
+
+        let dir = &std::env::temp_dir();
+        assert!(matches!(load(dir), Err(FileError::IsDirectory)));
+        assert!(matches!(save(dir, String::new()), Err(FileError::IsDirectory)));
+    }
 }
@@ -1,75 +1,135 @@
+use crate::config::EscapePolicy;
 use crate::text_buffer::line::Line;
 use crate::window::Window;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Why a grapheme is shown escaped rather than as itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscapeClass {
+    /// Control characters, which cannot be shown.
+    Control,
+    /// Bidirectional formatting characters, which can reorder the text around them.
+    Bidi,
+    /// Characters or clusters which take up no columns.
+    ZeroWidth,
+    /// Any other character outside of ASCII.
+    NonAscii,
+}
+
+/// Kind of whitespace a grapheme is drawn as a visible glyph in place of.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Whitespace {
+    Tab,
+    TrailingSpace,
+    NonBreakingSpace,
+}
 
-#[derive(Debug, Eq, PartialEq)]
+/// An extended grapheme cluster as it is displayed.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Grapheme {
     pub content: String,
-    pub is_escaped: bool,
+    /// Why `content` is the escaped form of the cluster, if it is.
+    pub escape: Option<EscapeClass>,
+    /// Whitespace `content` is a visible glyph in place of, if any.
+    pub whitespace: Option<Whitespace>,
 }
 
-pub fn visible_in_window(graphemes: &Vec<Grapheme>, window: &Window) -> Vec<Grapheme> {
-    if graphemes.is_empty() {
-        return vec![];
+/// Caret notation for a C0 control character or delete, such as `^M` for a carriage return,
+/// or angle bracket form for any other character.
+fn escape_char(ch: char) -> String {
+    match ch as u32 {
+        c @ 0x00..=0x1f | c @ 0x7f => format!("^{}", (c as u8 ^ 0x40) as char),
+        c => format!("<{:x}>", c),
     }
+}
 
-    let mut visible_graphemes = Vec::new();
-    let mut total_width_count = 0usize;
-    let mut width_count_at_first = 0usize;
-    for grapheme in graphemes.iter() {
-        if grapheme.len() + total_width_count < window.horizontal_offset {
-            total_width_count += grapheme.len();
-            continue;
-        }
-
-        if total_width_count >= window.right() {
-            break;
-        }
+fn is_bidi(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}'
+    )
+}
 
-        if visible_graphemes.is_empty() {
-            width_count_at_first = total_width_count;
-        }
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}')
+}
 
-        total_width_count += grapheme.len();
-        visible_graphemes.push(Grapheme {
-            content: grapheme.content.clone(),
-            is_escaped: grapheme.is_escaped,
-        });
+/// Class the cluster must be escaped as under `policy`, if any.
+fn escape_class(cluster: &str, policy: EscapePolicy) -> Option<EscapeClass> {
+    if cluster.chars().any(|c| c.is_control()) {
+        return Some(EscapeClass::Control);
     }
-
-    let first_grapheme = match visible_graphemes.first_mut() {
-        Some(g) => g,
-        None => return visible_graphemes,
-    };
-    let trim_count = window.horizontal_offset - width_count_at_first;
-    // Trim characters off front of first grapheme if horizontal offset sits within
-    let trimmed_content = first_grapheme
-        .content
-        .chars()
-        .skip(trim_count)
-        .collect::<String>();
-    first_grapheme.content = trimmed_content;
-    if first_grapheme.content.is_empty() {
-        visible_graphemes.remove(0);
+    if cluster.width() == 0 {
+        return match cluster.chars().any(is_bidi) {
+            true => Some(EscapeClass::Bidi),
+            false => Some(EscapeClass::ZeroWidth),
+        };
+    }
+    if policy == EscapePolicy::Native {
+        return None;
     }
 
-    // Truncate last grapheme if window ends within
-    let width_of_visible = total_width_count - width_count_at_first - trim_count;
-    if width_of_visible > window.width as usize {
-        let last_grapheme = match visible_graphemes.last_mut() {
-            Some(g) => g,
-            None => return visible_graphemes,
-        };
+    if cluster.chars().any(is_bidi) {
+        return Some(EscapeClass::Bidi);
+    }
+    // A zero width joiner within a non-ASCII cluster is most likely joining an emoji sequence
+    let starts_ascii = cluster.chars().next().is_none_or(|c| c.is_ascii());
+    let is_hidden = |c: char| is_zero_width(c) && (c != '\u{200d}' || starts_ascii);
+    if cluster.chars().any(is_hidden) {
+        return Some(EscapeClass::ZeroWidth);
+    }
+    match (policy, cluster.is_ascii()) {
+        (EscapePolicy::Ascii, false) => Some(EscapeClass::NonAscii),
+        _ => None,
+    }
+}
 
-        let trim_count = width_of_visible - window.width as usize;
-        let trimmed_content = last_grapheme
+/// Part of `grapheme` covering `columns`, relative to its first column. Escaped and other
+/// single-column-per-character content is cut by character, whereas a wide character which is
+/// only partly visible is replaced by blanks.
+fn clip(grapheme: &Grapheme, columns: std::ops::Range<usize>) -> Grapheme {
+    let content = match grapheme.width() == grapheme.content.chars().count() {
+        true => grapheme
             .content
             .chars()
-            .take(last_grapheme.content.chars().count() - trim_count)
-            .collect::<String>();
-        last_grapheme.content = trimmed_content;
+            .skip(columns.start)
+            .take(columns.len())
+            .collect::<String>(),
+        false => vec![' '; columns.len()].into_iter().collect(),
+    };
+
+    Grapheme {
+        content,
+        escape: grapheme.escape,
+        whitespace: grapheme.whitespace,
+    }
+}
+
+pub fn visible_in_window(graphemes: &[Grapheme], window: &Window) -> Vec<Grapheme> {
+    let mut visible_graphemes = Vec::new();
+    let mut column = 0usize;
+    for grapheme in graphemes.iter() {
+        let start = column;
+        let end = column + grapheme.width();
+        column = end;
+        if end <= window.horizontal_offset {
+            continue;
+        }
+        if start >= window.right() {
+            break;
+        }
 
-        if last_grapheme.content.is_empty() {
-            visible_graphemes.pop();
+        let visible = std::cmp::max(start, window.horizontal_offset)..std::cmp::min(end, window.right());
+        if visible == (start..end) {
+            visible_graphemes.push(Grapheme {
+                content: grapheme.content.clone(),
+                escape: grapheme.escape,
+                whitespace: grapheme.whitespace,
+            });
+        } else {
+            // Trim columns off a grapheme the edge of the window sits within
+            visible_graphemes.push(clip(grapheme, visible.start - start..visible.end - start));
         }
     }
 
@@ -77,45 +137,62 @@ pub fn visible_in_window(graphemes: &Vec<Grapheme>, window: &Window) -> Vec<Grap
 }
 
 impl Grapheme {
-    // No robust way that I know of to determine the visual width of a grapheme (cluster).
-    // Instead, any unicode characters beyond latin-1 set will be escaped to angle bracket form.
-    pub fn from(ch: char, tab_width: u8) -> Grapheme {
-        match ch {
-            '\t' => Grapheme {
+    /// Grapheme for the cluster `cluster`, escaped to angle bracket form if `policy` requires.
+    pub fn from(cluster: &str, tab_width: u8, policy: EscapePolicy) -> Grapheme {
+        if cluster == "\t" {
+            return Grapheme {
                 content: vec![' '; tab_width as usize].into_iter().collect(),
-                is_escaped: false,
-            },
-            ch if ch < 'ǿ' => Grapheme {
-                content: ch.to_string(),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
+            };
+        }
+
+        match escape_class(cluster, policy) {
+            None => Grapheme {
+                content: cluster.to_string(),
+                escape: None,
+                whitespace: None,
             },
-            _ => {
-                let unicode = ch
-                    .escape_unicode()
-                    .skip(3)
-                    .take_while(|c| *c != '}')
-                    .collect::<String>();
-                let formatted = format!("<{}>", unicode);
+            Some(class) => {
+                let formatted = cluster.chars().map(escape_char).collect::<String>();
 
                 Grapheme {
                     content: formatted,
-                    is_escaped: true,
+                    escape: Some(class),
+                    whitespace: None,
                 }
             }
         }
     }
 
-    pub fn from_line(line: &Line, tab_width: u8) -> Vec<Grapheme> {
-        let mut graphemes = vec![];
-        for ch in line.content.chars() {
-            graphemes.push(Grapheme::from(ch, tab_width));
-        }
+    pub fn from_line(line: &Line, tab_width: u8, policy: EscapePolicy) -> Vec<Grapheme> {
+        line.content
+            .graphemes(true)
+            .map(|cluster| Grapheme::from(cluster, tab_width, policy))
+            .collect()
+    }
 
-        graphemes
+    /// Replaces tabs, spaces trailing at the end of the line and non-breaking spaces amongst
+    /// `graphemes`, which must be those of `line`, with visible glyphs of the same width.
+    pub fn show_whitespace(line: &Line, graphemes: &mut [Grapheme]) {
+        let trailing_start = line.content.trim_end_matches(' ').len();
+        for ((offset, cluster), grapheme) in line.content.grapheme_indices(true).zip(graphemes.iter_mut()) {
+            let (glyph, whitespace) = match cluster {
+                "\t" => ('→', Whitespace::Tab),
+                " " if offset >= trailing_start => ('·', Whitespace::TrailingSpace),
+                "\u{a0}" => ('⍽', Whitespace::NonBreakingSpace),
+                _ => continue,
+            };
+
+            let padding = grapheme.width().saturating_sub(1);
+            grapheme.content = format!("{}{}", glyph, " ".repeat(padding));
+            grapheme.whitespace = Some(whitespace);
+        }
     }
 
-    pub fn len(&self) -> usize {
-        self.content.len()
+    /// Number of columns the grapheme takes up when displayed.
+    pub fn width(&self) -> usize {
+        self.content.width()
     }
 }
 
@@ -128,70 +205,79 @@ mod tests {
         let graphemes = &vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
 
-        let window = &mut Window {
-            height: 5,
-            width: 9,
-            horizontal_offset: 0,
-            vertical_offset: 0,
-        };
+        let window = &mut Window::new(5, 9, 0, 0);
 
         let visible_graphemes = super::visible_in_window(graphemes, window);
         let expected_visible_graphemes = vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
         assert_eq!(visible_graphemes, expected_visible_graphemes);
@@ -202,67 +288,75 @@ mod tests {
         let graphemes = &vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
 
-        let window = &mut Window {
-            height: 5,
-            width: 9,
-            horizontal_offset: 0,
-            vertical_offset: 0,
-        };
+        let window = &mut Window::new(5, 9, 0, 0);
 
         window.width = 6;
         let visible_graphemes = super::visible_in_window(graphemes, window);
         let expected_visible_graphemes = vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
         assert_eq!(visible_graphemes, expected_visible_graphemes);
@@ -273,39 +367,44 @@ mod tests {
         let graphemes = &vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
 
         let window = &mut Window {
-            height: 5,
-            width: 7,
             horizontal_offset: 3,
-            vertical_offset: 0,
+            ..Window::new(5, 7, 0, 0)
         };
 
         window.width = 9;
@@ -313,19 +412,23 @@ mod tests {
         let expected_visible_graphemes = vec![
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
         assert_eq!(visible_graphemes, expected_visible_graphemes);
@@ -336,39 +439,44 @@ mod tests {
         let graphemes = &vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
 
         let window = &mut Window {
-            height: 5,
-            width: 9,
             horizontal_offset: 10,
-            vertical_offset: 0,
+            ..Window::new(5, 9, 0, 0)
         };
 
         window.width = 9;
@@ -379,33 +487,110 @@ mod tests {
 
     #[test]
     fn tab_width() {
-        let grapheme = Grapheme::from('\t', 4);
+        let grapheme = Grapheme::from("\t", 4, EscapePolicy::Native);
         assert_eq!(grapheme.content, String::from("    "));
 
-        let grapheme = Grapheme::from('\t', 8);
+        let grapheme = Grapheme::from("\t", 8, EscapePolicy::Native);
         assert_eq!(grapheme.content, String::from("        "));
     }
 
+    #[test]
+    fn clusters_and_widths() {
+        let line = Line::new(0, String::from("e\u{301}👨‍👩‍👧中\u{1}"));
+        let graphemes = Grapheme::from_line(&line, 4, EscapePolicy::Native);
+
+        let contents = graphemes.iter().map(|g| g.content.as_str()).collect::<Vec<&str>>();
+        assert_eq!(vec!["e\u{301}", "👨‍👩‍👧", "中", "^A"], contents);
+        let widths = graphemes.iter().map(|g| g.width()).collect::<Vec<usize>>();
+        assert_eq!(vec![1, 2, 2, 2], widths);
+        assert_eq!(Some(EscapeClass::Control), graphemes[3].escape);
+    }
+
+    #[test]
+    fn control_caret_notation() {
+        let line = Line::new(0, String::from("\u{0}\u{1b}\u{c}\u{7f}\u{85}\r"));
+        let graphemes = Grapheme::from_line(&line, 4, EscapePolicy::Native);
+
+        let contents = graphemes.iter().map(|g| g.content.as_str()).collect::<Vec<&str>>();
+        assert_eq!(vec!["^@", "^[", "^L", "^?", "<85>", "^M"], contents);
+        assert!(graphemes.iter().all(|g| g.escape == Some(EscapeClass::Control)));
+    }
+
+    #[test]
+    fn visible_whitespace() {
+        let line = Line::new(0, String::from("\ta b\u{a0}c  "));
+        let graphemes = &mut Grapheme::from_line(&line, 4, EscapePolicy::Native);
+        Grapheme::show_whitespace(&line, graphemes);
+
+        let contents = graphemes.iter().map(|g| g.content.as_str()).collect::<String>();
+        assert_eq!("→   a b⍽c··", contents);
+        let kinds = graphemes.iter().map(|g| g.whitespace).collect::<Vec<Option<Whitespace>>>();
+        assert_eq!(
+            vec![
+                Some(Whitespace::Tab),
+                None,
+                None,
+                None,
+                Some(Whitespace::NonBreakingSpace),
+                None,
+                Some(Whitespace::TrailingSpace),
+                Some(Whitespace::TrailingSpace),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn escape_policies() {
+        let escape = |cluster: &str, policy: EscapePolicy| Grapheme::from(cluster, 4, policy).escape;
+
+        assert_eq!(None, escape("é", EscapePolicy::Native));
+        assert_eq!(None, escape("é", EscapePolicy::Suspicious));
+        assert_eq!(Some(EscapeClass::NonAscii), escape("é", EscapePolicy::Ascii));
+        assert_eq!(None, escape("a", EscapePolicy::Ascii));
+
+        assert_eq!(None, escape("a\u{202e}", EscapePolicy::Native));
+        assert_eq!(Some(EscapeClass::Bidi), escape("a\u{202e}", EscapePolicy::Suspicious));
+        assert_eq!(Some(EscapeClass::Bidi), escape("\u{202e}", EscapePolicy::Native));
+        assert_eq!(Some(EscapeClass::Bidi), escape("\u{202e}", EscapePolicy::Suspicious));
+        assert_eq!(Some(EscapeClass::Bidi), escape("\u{202e}", EscapePolicy::Ascii));
+
+        // A combining mark with nothing to combine with takes up no columns regardless of policy
+        assert_eq!(Some(EscapeClass::ZeroWidth), escape("\u{301}", EscapePolicy::Native));
+        assert_eq!(Some(EscapeClass::ZeroWidth), escape("\u{200b}", EscapePolicy::Suspicious));
+        assert_eq!(None, escape("a\u{200d}", EscapePolicy::Native));
+        assert_eq!(Some(EscapeClass::ZeroWidth), escape("a\u{200d}", EscapePolicy::Suspicious));
+        assert_eq!(None, escape("👨‍👩‍👧", EscapePolicy::Suspicious));
+        assert_eq!(Some(EscapeClass::NonAscii), escape("👨‍👩‍👧", EscapePolicy::Ascii));
+
+        assert_eq!(Some(EscapeClass::Control), escape("\u{7f}", EscapePolicy::Native));
+        assert_eq!("<1f468><200d><1f469>", Grapheme::from("👨‍👩", 4, EscapePolicy::Ascii).content);
+    }
+
     #[test]
     fn trimmed_escaped_graphemes() {
-        let window = &mut Window {
-            height: 5,
-            width: 6,
-            horizontal_offset: 0,
-            vertical_offset: 0,
-        };
+        let window = &mut Window::new(5, 6, 0, 0);
 
-        let graphemes = &String::from("👨‍👩‍👧 ")
-            .chars()
-            .map(|c| Grapheme::from(c, 1))
+        // Combining mark and zero width joiner with nothing to attach to
+        let graphemes = &vec!["\u{301}", "\u{200d}", " "]
+            .into_iter()
+            .map(|c| Grapheme::from(c, 1, EscapePolicy::Native))
             .collect::<Vec<Grapheme>>();
 
         window.horizontal_offset = 0;
         let visible_graphemes = super::visible_in_window(&graphemes, window);
-        let expected_visible_graphemes = vec![Grapheme {
-            content: String::from("<1f468"),
-            is_escaped: true,
-        }];
+        let expected_visible_graphemes = vec![
+            Grapheme {
+                content: String::from("<301>"),
+                escape: Some(EscapeClass::ZeroWidth),
+                whitespace: None,
+            },
+            Grapheme {
+                content: String::from("<"),
+                escape: Some(EscapeClass::ZeroWidth),
+                whitespace: None,
+            },
+        ];
         assert_eq!(visible_graphemes, expected_visible_graphemes);
 
         window.width = 9;
@@ -413,12 +598,44 @@ mod tests {
         let visible_graphemes = super::visible_in_window(&graphemes, window);
         let expected_visible_graphemes = vec![
             Grapheme {
-                content: String::from("468>"),
-                is_escaped: true,
+                content: String::from("1>"),
+                escape: Some(EscapeClass::ZeroWidth),
+                whitespace: None,
+            },
+            Grapheme {
+                content: String::from("<200d>"),
+                escape: Some(EscapeClass::ZeroWidth),
+                whitespace: None,
+            },
+            Grapheme {
+                content: String::from(" "),
+                escape: None,
+                whitespace: None,
+            },
+        ];
+        assert_eq!(visible_graphemes, expected_visible_graphemes);
+    }
+
+    #[test]
+    fn trimmed_wide_graphemes_blanked() {
+        let window = &mut Window {
+            horizontal_offset: 1,
+            ..Window::new(5, 3, 0, 0)
+        };
+
+        let line = Line::new(0, String::from("中文字"));
+        let graphemes = &Grapheme::from_line(&line, 4, EscapePolicy::Native);
+        let visible_graphemes = super::visible_in_window(&graphemes, window);
+        let expected_visible_graphemes = vec![
+            Grapheme {
+                content: String::from(" "),
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
-                content: String::from("<200d"),
-                is_escaped: true,
+                content: String::from("文"),
+                escape: None,
+                whitespace: None,
             },
         ];
         assert_eq!(visible_graphemes, expected_visible_graphemes);
@@ -429,50 +646,57 @@ mod tests {
         let graphemes = &vec![
             Grapheme {
                 content: String::from("a"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("b"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("c"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("f"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("g"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
 
         let window = &mut Window {
-            height: 5,
-            width: 2,
             horizontal_offset: 3,
-            vertical_offset: 0,
+            ..Window::new(5, 2, 0, 0)
         };
 
         let visible_graphemes = super::visible_in_window(graphemes, window);
         let expected_visible_graphemes = vec![
             Grapheme {
                 content: String::from("d"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
             Grapheme {
                 content: String::from("e"),
-                is_escaped: false,
+                escape: None,
+                whitespace: None,
             },
         ];
         assert_eq!(visible_graphemes, expected_visible_graphemes);
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Text cut or copied to the kill ring.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Kill {
+    pub text: String,
+    /// Whether `text` is made up of whole lines, which are pasted above the cursor's line rather
+    /// than at the cursor.
+    pub linewise: bool,
+}
+
+/// Where a kill was last pasted, so that it can be swapped for an earlier kill.
+pub struct Yank {
+    pub range: Range<usize>,
+    /// Index of the pasted kill within the ring.
+    pub index: usize,
+}
+
+/// Most recently cut or copied text, newest first, up to a fixed number of entries.
+pub struct KillRing {
+    kills: VecDeque<Kill>,
+    capacity: usize,
+    pub yank: Option<Yank>,
+}
+
+impl KillRing {
+    pub fn new(capacity: usize) -> KillRing {
+        KillRing {
+            kills: VecDeque::with_capacity(capacity),
+            capacity,
+            yank: None,
+        }
+    }
+
+    /// Adds `kill` as the newest entry, dropping the oldest if the ring is full.
+    pub fn push(&mut self, kill: Kill) {
+        if self.kills.len() == self.capacity {
+            self.kills.pop_back();
+        }
+        self.kills.push_front(kill);
+        self.yank = None;
+    }
+
+    /// Entry `index` places back from the newest.
+    pub fn get(&self, index: usize) -> Option<&Kill> {
+        self.kills.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.kills.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kills.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kill(text: &str) -> Kill {
+        Kill {
+            text: String::from(text),
+            linewise: false,
+        }
+    }
+
+    #[test]
+    fn newest_first() {
+        let ring = &mut KillRing::new(3);
+        assert!(ring.is_empty());
+
+        ring.push(kill("a"));
+        ring.push(kill("b"));
+        assert_eq!(Some(&kill("b")), ring.get(0));
+        assert_eq!(Some(&kill("a")), ring.get(1));
+        assert_eq!(None, ring.get(2));
+    }
+
+    #[test]
+    fn oldest_dropped_when_full() {
+        let ring = &mut KillRing::new(2);
+        ring.push(kill("a"));
+        ring.push(kill("b"));
+        ring.push(kill("c"));
+
+        assert_eq!(2, ring.len());
+        assert_eq!(Some(&kill("c")), ring.get(0));
+        assert_eq!(Some(&kill("b")), ring.get(1));
+    }
+}
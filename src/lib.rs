@@ -1,10 +1,18 @@
+pub mod backend;
+pub mod clipboard;
 pub mod commands;
 pub mod config;
 pub mod cursor;
 pub mod editor;
+pub mod event_loop;
 pub mod file;
 pub mod grapheme;
+pub mod kill_ring;
 pub mod renderer;
+pub mod replace;
+pub mod search;
+pub mod status;
 pub mod str_utils;
 pub mod text_buffer;
-pub mod window;
\ No newline at end of file
+pub mod window;
+pub mod wrap;
\ No newline at end of file
@@ -1,21 +1,65 @@
+use crate::backend::{Backend, Style};
 use crate::config::{EditorConfig, IndentationPreference};
 use crate::cursor::Cursor;
 use crate::grapheme;
+use crate::replace::{self, Replace, ReplaceStage};
+use crate::search::{self, Search};
+use crate::status::{MessageKind, Status};
+use crate::str_utils;
 use crate::text_buffer;
 use crate::window::Window;
+use crate::wrap;
 
-use crossterm::{
-    cursor::{Hide, MoveTo, Show},
-    queue, style,
-    style::{style, Color, StyledContent},
-    terminal,
-    terminal::{Clear, ClearType},
-};
-use grapheme::Grapheme;
-use std::io::Write;
+use crossterm::style::Color;
+use grapheme::{EscapeClass, Grapheme, Whitespace};
+use std::ops::Range;
 use text_buffer::{line::Line, TextBuffer};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 const MIN_WIDTH_LINE_NUMBER: u16 = 3;
+/// Fewest columns left for text beside the line numbers, below which the gutter is hidden.
+const MIN_WIDTH_TEXT: u16 = 8;
+/// Fewest rows the terminal must have for one to be given over to the status line.
+const MIN_HEIGHT_STATUS_LINE: u16 = 2;
+const SELECTION_COLOR: Color = Color::Rgb {
+    r: 76,
+    g: 86,
+    b: 106,
+};
+
+const MATCH_COLOR: Color = Color::Rgb {
+    r: 94,
+    g: 129,
+    b: 172,
+};
+
+/// What `render` draws: the text buffer with its cursors, any prompt being edited and the
+/// status bar.
+pub struct RenderContext<'a> {
+    pub text_buffer: &'a dyn TextBuffer,
+    pub cursor: &'a Cursor,
+    pub secondary_cursors: &'a [Cursor],
+    pub editor_config: &'a EditorConfig,
+    pub search: Option<&'a Search>,
+    pub replace: Option<&'a Replace>,
+    pub status: Status<'a>,
+}
+
+/// Colour `grapheme` is drawn in, distinguishing each class of escaped grapheme and each kind of
+/// visible whitespace.
+fn grapheme_color(grapheme: &Grapheme) -> Color {
+    match (grapheme.escape, grapheme.whitespace) {
+        (Some(EscapeClass::Control), _) => Color::Red,
+        (Some(EscapeClass::Bidi), _) => Color::Magenta,
+        (Some(EscapeClass::ZeroWidth), _) => Color::Cyan,
+        (Some(EscapeClass::NonAscii), _) => Color::Yellow,
+        (None, Some(Whitespace::Tab)) => Color::DarkGrey,
+        (None, Some(Whitespace::TrailingSpace)) => Color::DarkRed,
+        (None, Some(Whitespace::NonBreakingSpace)) => Color::DarkYellow,
+        (None, None) => Color::White,
+    }
+}
 
 struct TerminalCursorPosition {
     row: usize,
@@ -29,7 +73,7 @@ fn calc_absolute_cursor_position(
     let column = current_line_graphemes
         .iter()
         .take(cursor.character)
-        .map(|g| g.len())
+        .map(|g| g.width())
         .sum();
 
     TerminalCursorPosition {
@@ -50,6 +94,131 @@ fn renderable_lines(text_buffer: &dyn TextBuffer, window: &Window) -> Vec<(usize
     line_range.map(|x| (x, text_buffer.line_at(x))).collect()
 }
 
+/// Columns of `line` covered by `selection`. A selection continuing past the end of the line
+/// also covers the column following the last grapheme, standing in for the line break.
+fn selected_columns(
+    line: &Line,
+    graphemes: &[Grapheme],
+    selection: &Option<Range<usize>>,
+) -> Range<usize> {
+    let selection = match selection {
+        Some(selection) => selection,
+        None => return 0..0,
+    };
+    let line_end = line.start_index + line.len();
+    if selection.end <= line.start_index || selection.start > line_end {
+        return 0..0;
+    }
+
+    let to_column = |byte_offset: usize| -> usize {
+        let character = str_utils::grapheme_count(&line.content[..byte_offset]);
+        graphemes.iter().take(character).map(|g| g.width()).sum()
+    };
+    let start = to_column(selection.start.saturating_sub(line.start_index));
+    let end = to_column(std::cmp::min(selection.end, line_end) - line.start_index);
+
+    match selection.end > line_end {
+        true => start..end + 1,
+        false => start..end,
+    }
+}
+
+/// Columns of `line` at which one of `cursors` sits.
+fn cursor_columns(line_index: usize, graphemes: &Vec<Grapheme>, cursors: &[Cursor]) -> Vec<usize> {
+    cursors
+        .iter()
+        .filter(|c| c.line == line_index)
+        .map(|c| calc_absolute_cursor_position(c, graphemes).column)
+        .collect()
+}
+
+/// Text of the search prompt, up to and including the query, followed by any status suffix.
+fn search_prompt(search: &Search) -> (String, String) {
+    let prompt = match search.case_sensitive {
+        true => format!("Find (Aa): {}", search.query),
+        false => format!("Find: {}", search.query),
+    };
+    let status = match (&search.current_match, search.query.is_empty()) {
+        (None, false) => String::from("  (no matches)"),
+        _ => String::new(),
+    };
+
+    (prompt, status)
+}
+
+/// Text of the replace prompt, up to and including the text being edited, followed by a preview
+/// of the pending replacement or any other status suffix.
+fn replace_prompt(replace: &Replace) -> (String, String) {
+    let prompt = match (replace.stage, &replace.pending) {
+        (ReplaceStage::Pattern, _) => format!("Replace: {}", replace.pattern),
+        (ReplaceStage::Replacement, _) => {
+            format!("Replace: {} With: {}", replace.pattern, replace.replacement)
+        }
+        (ReplaceStage::Confirm, Some(pending)) => {
+            format!("Replace with \"{}\"? (y/n/a/q) ", pending.text)
+        }
+        (ReplaceStage::Confirm, None) => String::from("Replace: "),
+    };
+    let status = match (&replace.regex, &replace.pending, replace.pattern.is_empty()) {
+        (_, _, true) => String::new(),
+        (Err(_), _, false) => String::from("  (invalid pattern)"),
+        (Ok(_), None, false) => String::from("  (no matches)"),
+        (Ok(_), Some(pending), false) => match replace.stage {
+            ReplaceStage::Replacement => format!("  -> \"{}\"", pending.text),
+            _ => String::new(),
+        },
+    };
+
+    (prompt, status)
+}
+
+/// Description of the file for the status bar: its name, then markers for being read-only and
+/// having unsaved changes.
+fn file_description(status: &Status) -> (String, String) {
+    let name = String::from(status.file_name.unwrap_or("[No Name]"));
+    let read_only = match status.read_only {
+        true => " [RO]",
+        false => "",
+    };
+    let modified = match status.modified {
+        true => " [+]",
+        false => "",
+    };
+
+    (name, format!("{}{}", read_only, modified))
+}
+
+/// Longest prefix of `s`, in whole grapheme clusters, no wider than `width` columns.
+fn truncate_to_width(s: &str, width: usize) -> &str {
+    let mut columns = 0;
+    let end = s
+        .grapheme_indices(true)
+        .find(|(_, g)| {
+            columns += g.width();
+            columns > width
+        })
+        .map_or(s.len(), |(i, _)| i);
+    &s[..end]
+}
+
+/// Indentation mode, line ending and line count of the file, for the status bar.
+fn file_details(
+    text_buffer: &dyn TextBuffer,
+    editor_config: &EditorConfig,
+    status: &Status,
+) -> String {
+    let indentation = match editor_config.indentation {
+        IndentationPreference::Tabs => String::from("Tabs"),
+        IndentationPreference::Spaces => format!("Spaces: {}", editor_config.tab_width),
+    };
+    let line_count = match text_buffer.line_count() {
+        1 => String::from("1 line"),
+        count => format!("{} lines", count),
+    };
+
+    format!("{}  {}  {}  ", indentation, status.line_ending.name(), line_count)
+}
+
 fn get_cursor_position_info(
     cursor: &Cursor,
     absolute_cursor_position: &TerminalCursorPosition,
@@ -66,96 +235,553 @@ fn get_cursor_position_info(
     }
 }
 
-pub fn render(
-    screen: &mut impl Write,
+/// Scrolls `window` by whole lines so that the row of the wrapped line the cursor is on is in view,
+/// returning how many rows of the first line must also be skipped if it alone is too tall.
+fn scroll_to_wrapped_cursor(
     text_buffer: &dyn TextBuffer,
     cursor: &Cursor,
     window: &mut Window,
-    editor_config: &EditorConfig
-) {
-    queue!(screen, Clear(ClearType::All), Hide);
+    editor_config: &EditorConfig,
+) -> usize {
+    window.horizontal_offset = 0;
+    if cursor.line < window.vertical_offset {
+        window.vertical_offset = cursor.line;
+    }
+
+    let rows = |line_index: usize| {
+        let line = text_buffer.line_at(line_index);
+        let graphemes = Grapheme::from_line(&line, editor_config.tab_width, editor_config.escape);
+        wrap::wrap(&graphemes, window.width as usize)
+    };
+    let cursor_rows = rows(cursor.line);
+    // Rows from the top of the window down to and including the one the cursor is on
+    let mut rows_to_cursor = wrap::row_of(&cursor_rows, cursor.character) + 1;
+    let mut line_row_counts = (window.vertical_offset..cursor.line)
+        .map(|i| rows(i).len())
+        .collect::<std::collections::VecDeque<usize>>();
+    rows_to_cursor += line_row_counts.iter().sum::<usize>();
+    while rows_to_cursor > window.height as usize {
+        match line_row_counts.pop_front() {
+            Some(count) => {
+                rows_to_cursor -= count;
+                window.vertical_offset += 1;
+            }
+            None => return rows_to_cursor - window.height as usize,
+        }
+    }
+
+    0
+}
+
+/// Line and character shown at terminal `column` and `row` as of the last render. Positions past
+/// the end of a row are at the end of that row, and those below the last line at its end.
+pub fn position_at(
+    text_buffer: &dyn TextBuffer,
+    window: &Window,
+    editor_config: &EditorConfig,
+    column: u16,
+    row: u16,
+) -> (usize, usize) {
+    let column = column.saturating_sub(window.gutter_width) as usize;
+    // Rows from the first row of the line at the top of the window down to the one clicked
+    let mut rows_above = window.skipped_rows + row as usize;
+    for line_index in window.vertical_offset..text_buffer.line_count() {
+        let line = text_buffer.line_at(line_index);
+        let graphemes = Grapheme::from_line(&line, editor_config.tab_width, editor_config.escape);
+        let (line_rows, column) = match editor_config.soft_wrap {
+            true => (wrap::wrap(&graphemes, window.width as usize), column),
+            false => (
+                std::iter::once(0..graphemes.len()).collect(),
+                window.horizontal_offset + column,
+            ),
+        };
+        if rows_above < line_rows.len() {
+            let character = wrap::character_at_column(&graphemes, &line_rows, rows_above, column);
+            return (line_index, character);
+        }
+        rows_above -= line_rows.len();
+    }
+
+    let last_line = text_buffer.line_count() - 1;
+    let character = str_utils::grapheme_count(&text_buffer.line_at(last_line).content);
+    (last_line, character)
+}
 
-    let (terminal_width, terminal_height) = terminal::size().expect("Failed to get terminal size.");
-    let line_number_columns = line_number_width(text_buffer.line_count());
-    window.resize(terminal_height - 1, terminal_width - line_number_columns);
+pub fn render(backend: &mut dyn Backend, window: &mut Window, context: &RenderContext) {
+    let RenderContext {
+        text_buffer,
+        cursor,
+        secondary_cursors,
+        editor_config,
+        search,
+        replace,
+        ref status,
+    } = *context;
+    backend.clear();
+    backend.hide_cursor();
+
+    let (terminal_width, terminal_height) = backend.size();
+    if terminal_width == 0 || terminal_height == 0 {
+        backend.flush();
+        return;
+    }
+
+    // Very small terminals give up the gutter and status line to leave room for the text
+    let line_number_columns = match line_number_width(text_buffer.line_count()) {
+        columns if columns + MIN_WIDTH_TEXT <= terminal_width => columns,
+        _ => 0,
+    };
+    let has_status_line = terminal_height >= MIN_HEIGHT_STATUS_LINE;
+    let status_line_rows = has_status_line as u16;
+    window.resize(terminal_height - status_line_rows, terminal_width - line_number_columns);
+    window.gutter_width = line_number_columns;
 
     let current_line = text_buffer.line_at(cursor.line);
-    let graphemes = &Grapheme::from_line(&current_line, editor_config.tab_width);
+    let graphemes = &Grapheme::from_line(&current_line, editor_config.tab_width, editor_config.escape);
     let absolute_cursor_position = &calc_absolute_cursor_position(cursor, graphemes);
-    window.update_offsets(
-        absolute_cursor_position.row,
-        absolute_cursor_position.column,
-    );
+    if window.follow_cursor {
+        window.skipped_rows = match editor_config.soft_wrap {
+            true => scroll_to_wrapped_cursor(text_buffer, cursor, window, editor_config),
+            false => {
+                window.update_offsets(
+                    absolute_cursor_position.row,
+                    absolute_cursor_position.column,
+                );
+                0
+            }
+        };
+    }
 
-    let mut line_count = 0;
-    for (line_index, line) in renderable_lines(text_buffer, window) {
+    let selections = secondary_cursors
+        .iter()
+        .chain(std::iter::once(cursor))
+        .map(|c| c.selection(text_buffer))
+        .collect::<Vec<Option<Range<usize>>>>();
+    let lines = renderable_lines(text_buffer, window);
+    let visible_range = match (lines.first(), lines.last()) {
+        (Some((_, first)), Some((_, last))) => first.start_index..last.start_index + last.len() + 1,
+        _ => 0..0,
+    };
+    let matches = match (search, replace.and_then(|r| r.regex())) {
+        (Some(search), _) => search::find_all(
+            text_buffer,
+            &search.query,
+            search.case_sensitive,
+            visible_range,
+        ),
+        (None, Some(regex)) => replace::find_all(text_buffer, regex, "", visible_range)
+            .into_iter()
+            .map(|r| r.range)
+            .collect(),
+        (None, None) => Vec::new(),
+    };
+    let mut row = 0u16;
+    // Screen position of the cursor, unless the window has been scrolled away from it
+    let mut cursor_position = None;
+    for (line_index, line) in lines {
         let mut background_color = Color::Reset;
-
         if line_index == cursor.line {
             background_color = Color::Rgb {
                 r: 59,
                 g: 66,
                 b: 82,
             };
-            let characters = (0..terminal_width).map(|_| ' ').collect::<String>();
-            let styled_characters = style(characters).on(background_color);
-            queue!(
-                screen,
-                MoveTo(0, line_count as u16),
-                style::PrintStyledContent(styled_characters)
-            );
         }
 
-        let characters = format!(
-            "{:>min_width$}",
-            line_index + 1,
-            min_width = MIN_WIDTH_LINE_NUMBER as usize
-        );
-        let styled_characters = style(characters).with(Color::Blue).on(background_color);
-        queue!(
-            screen,
-            MoveTo(0, line_count as u16),
-            style::PrintStyledContent(styled_characters),
-            MoveTo(line_number_columns, line_count as u16)
-        );
-
-        let graphemes = &Grapheme::from_line(&line, editor_config.tab_width);
-        let graphemes = grapheme::visible_in_window(graphemes, window);
-        let styled_graphemes = graphemes
+        let graphemes = &mut Grapheme::from_line(&line, editor_config.tab_width, editor_config.escape);
+        if editor_config.show_whitespace {
+            Grapheme::show_whitespace(&line, graphemes);
+        }
+        let graphemes = &*graphemes;
+        let selection_columns = selections
+            .iter()
+            .map(|selection| selected_columns(&line, graphemes, selection))
+            .collect::<Vec<Range<usize>>>();
+        let match_columns = matches
             .iter()
-            .map(|g| match g.is_escaped {
-                true => style(&g.content).with(Color::Yellow).on(background_color),
-                false => style(&g.content).with(Color::White).on(background_color),
-            })
-            .collect::<Vec<StyledContent<&String>>>();
-
-        for styled in styled_graphemes {
-            queue!(screen, style::PrintStyledContent(styled));
+            .map(|m| selected_columns(&line, graphemes, &Some(m.clone())))
+            .collect::<Vec<Range<usize>>>();
+        let is_selected = |column: usize| selection_columns.iter().any(|c| c.contains(&column));
+        let is_match = |column: usize| match_columns.iter().any(|c| c.contains(&column));
+        let secondary_cursor_columns = cursor_columns(line_index, graphemes, secondary_cursors);
+
+        let line_rows = match editor_config.soft_wrap {
+            true => wrap::wrap(graphemes, window.width as usize),
+            false => std::iter::once(0..graphemes.len()).collect(),
+        };
+        let cursor_row = match line_index == cursor.line {
+            true => Some(wrap::row_of(&line_rows, cursor.character)),
+            false => None,
+        };
+        let skip = match line_index == window.vertical_offset {
+            true => window.skipped_rows,
+            false => 0,
+        };
+        for (line_row, range) in line_rows.iter().enumerate().skip(skip) {
+            if row >= window.height {
+                break;
+            }
+
+            if line_index == cursor.line {
+                let characters = (0..terminal_width).map(|_| ' ').collect::<String>();
+                backend.move_to(0, row);
+                backend.print(&characters, Style::new().on(background_color));
+            }
+
+            // Rows a wrapped line continues onto are marked in place of a line number
+            let characters = match line_row {
+                0 => format!("{:>min_width$}", line_index + 1, min_width = MIN_WIDTH_LINE_NUMBER as usize),
+                _ => format!("{:>min_width$}", '↪', min_width = MIN_WIDTH_LINE_NUMBER as usize),
+            };
+            if line_number_columns > 0 {
+                backend.move_to(0, row);
+                backend.print(&characters, Style::new().with(Color::Blue).on(background_color));
+            }
+            backend.move_to(line_number_columns, row);
+
+            let (visible_graphemes, start_column) = match editor_config.soft_wrap {
+                true => (
+                    graphemes[range.clone()].to_vec(),
+                    wrap::width_of(&graphemes[..range.start]),
+                ),
+                false => (
+                    grapheme::visible_in_window(graphemes, window),
+                    window.horizontal_offset,
+                ),
+            };
+            let mut column = start_column;
+            for g in visible_graphemes.iter() {
+                let background_color = match (is_selected(column), is_match(column)) {
+                    (true, _) => SELECTION_COLOR,
+                    (false, true) => MATCH_COLOR,
+                    (false, false) => background_color,
+                };
+                let style = Style::new().with(grapheme_color(g)).on(background_color);
+                let style = match secondary_cursor_columns.contains(&column) {
+                    true => style.reversed(),
+                    false => style,
+                };
+                backend.print(&g.content, style);
+                column += g.width();
+            }
+
+            let is_last_row = line_row + 1 == line_rows.len();
+            if is_last_row && column < start_column + window.width as usize {
+                // Cell following the end of the line, standing in for the line break
+                let background_color = match is_selected(column) {
+                    true => SELECTION_COLOR,
+                    false => background_color,
+                };
+                let style = match secondary_cursor_columns.contains(&column) {
+                    true => Style::new().on(background_color).reversed(),
+                    false => Style::new().on(background_color),
+                };
+                backend.print(" ", style);
+            }
+
+            let cursor_column = absolute_cursor_position
+                .column
+                .checked_sub(start_column)
+                .filter(|column| *column < window.width as usize);
+            if let (true, Some(column)) = (cursor_row == Some(line_row), cursor_column) {
+                cursor_position = Some((line_number_columns + column as u16, row));
+            }
+            row += 1;
+        }
+    }
+
+    if has_status_line {
+        let status_row = terminal_height - 1;
+        let exit_prompt = match status.confirming_exit {
+            true => Some((String::from("Save changes before exiting? (y/n/c) "), String::new())),
+            false => None,
+        };
+        let prompt = exit_prompt
+            .or_else(|| search.map(search_prompt))
+            .or_else(|| replace.map(replace_prompt));
+        let cursor_position_info = get_cursor_position_info(cursor, absolute_cursor_position);
+        backend.move_to(0, status_row);
+        let right = match prompt {
+            Some((prompt, prompt_status)) => {
+                let column = std::cmp::min(prompt.width(), terminal_width as usize - 1);
+                cursor_position = Some((column as u16, status_row));
+                backend.print(&prompt, Style::new());
+                backend.print(&prompt_status, Style::new().with(Color::Yellow));
+                cursor_position_info
+            }
+            None => {
+                let (name, modified) = file_description(status);
+                let mut left = vec![(name, Style::new()), (modified, Style::new().with(Color::Yellow))];
+                if let Some(message) = status.message {
+                    let color = match message.kind {
+                        MessageKind::Info => Color::Reset,
+                        MessageKind::Error => Color::Red,
+                    };
+                    left.push((String::from("  "), Style::new()));
+                    left.push((message.text.clone(), Style::new().with(color)));
+                }
+                let left_width = left.iter().map(|(text, _)| text.width()).sum::<usize>();
+
+                // The file details give way to the cursor position if there is not room for both
+                let details = file_details(text_buffer, editor_config, status);
+                let right_width = details.width() + cursor_position_info.width();
+                let right = match left_width + right_width < terminal_width as usize {
+                    true => details + &cursor_position_info,
+                    false => cursor_position_info,
+                };
+
+                // What does not fit of the left side is cut off, leaving a column between it and
+                // the right side
+                let mut room = (terminal_width as usize).saturating_sub(right.width() + 1);
+                for (text, style) in left {
+                    let text = truncate_to_width(&text, room);
+                    backend.print(text, style);
+                    room -= text.width();
+                }
+                right
+            }
+        };
+
+        backend.move_to(terminal_width.saturating_sub(right.width() as u16), status_row);
+        backend.print(&right, Style::new());
+    }
+
+    if let Some((column, row)) = cursor_position {
+        backend.show_cursor(column, row);
+    }
+    backend.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::memory::MemoryBackend;
+    use crate::config::{ClipboardProvider, EscapePolicy};
+    use crate::file::LineEnding;
+    use crate::status::StatusMessage;
+    use crate::text_buffer::piece_table::PieceTable;
+
+    fn config() -> EditorConfig {
+        EditorConfig {
+            tab_width: 4,
+            indentation: IndentationPreference::Tabs,
+            escape: EscapePolicy::Suspicious,
+            show_whitespace: false,
+            soft_wrap: false,
+            word_characters: String::from("_"),
+            clipboard: ClipboardProvider::None,
+        }
+    }
+
+    fn context<'a>(
+        text_buffer: &'a dyn TextBuffer,
+        cursor: &'a Cursor,
+        editor_config: &'a EditorConfig,
+    ) -> RenderContext<'a> {
+        RenderContext {
+            text_buffer,
+            cursor,
+            secondary_cursors: &[],
+            editor_config,
+            search: None,
+            replace: None,
+            status: Status::default(),
         }
+    }
+
+    fn render_to(
+        backend: &mut MemoryBackend,
+        text_buffer: &dyn TextBuffer,
+        cursor: &Cursor,
+        search: Option<&Search>,
+    ) {
+        let window = &mut Window::new(0, 0, 0, 0);
+        let config = &config();
+        render(backend, window, &RenderContext { search, ..context(text_buffer, cursor, config) });
+    }
+
+    #[test]
+    fn gutter_and_content() {
+        let pt = &PieceTable::new(String::from("fn main() {\n\t😀\u{7}\n}"));
+        let backend = &mut MemoryBackend::new(20, 4);
+        render_to(backend, pt, &Cursor::new(), None);
+
+        assert_eq!("  1 fn main() {     ", backend.row(0));
+        assert_eq!("  2     😀^G        ", backend.row(1));
+        assert_eq!("  3 }               ", backend.row(2));
+        assert_eq!(Color::Blue, backend.cell(2, 1).style.foreground);
+        assert_eq!(Color::White, backend.cell(8, 1).style.foreground);
+        assert_eq!(Color::Red, backend.cell(10, 1).style.foreground);
+        assert_eq!(Color::White, backend.cell(4, 0).style.foreground);
+    }
+
+    #[test]
+    fn cursor_and_status() {
+        let pt = &PieceTable::new(String::from("fn main() {\n\t😀\u{7}\n}"));
+        let backend = &mut MemoryBackend::new(20, 4);
+        render_to(backend, pt, &Cursor::new(), None);
+
+        assert_eq!(Some((4, 0)), backend.cursor());
+        assert_eq!("[No Name Ln 1, Col 1", backend.row(3));
 
-        line_count += 1;
+        // Cursor after the wide and escaped graphemes
+        let cursor = &mut Cursor::new();
+        cursor.move_to_offset(pt, 18);
+        render_to(backend, pt, cursor, None);
+
+        assert_eq!(Some((12, 1)), backend.cursor());
+        assert_eq!("[No Na Ln 2, Col 4-9", backend.row(3));
     }
 
-    let relative_cursor_row = absolute_cursor_position.row - window.vertical_offset;
-    let relative_cursor_column =
-        line_number_columns + ((absolute_cursor_position.column - window.horizontal_offset) as u16);
+    #[test]
+    fn status_bar_file_details_and_message() {
+        let pt = &PieceTable::new(String::from("a\r\nb"));
+        let backend = &mut MemoryBackend::new(71, 3);
+        let window = &mut Window::new(0, 0, 0, 0);
+        let config = &mut config();
+        config.indentation = IndentationPreference::Spaces;
+        let message = StatusMessage {
+            kind: MessageKind::Error,
+            text: String::from("Could not save"),
+        };
+        let status = Status {
+            file_name: Some("main.rs"),
+            modified: true,
+            read_only: true,
+            confirming_exit: false,
+            line_ending: LineEnding::Crlf,
+            message: Some(&message),
+        };
+        let cursor = &Cursor::new();
+        let frame = RenderContext {
+            status: status.clone(),
+            ..context(pt, cursor, config)
+        };
+        render(backend, window, &frame);
+
+        assert_eq!(
+            "main.rs [RO] [+]  Could not save  Spaces: 4  CRLF  2 lines  Ln 1, Col 1",
+            backend.row(2)
+        );
+        assert_eq!(Color::Yellow, backend.cell(9, 2).style.foreground);
+        assert_eq!(Color::Red, backend.cell(18, 2).style.foreground);
+
+        // Without room for everything, the message is cut short before the cursor position
+        let narrow_backend = &mut MemoryBackend::new(40, 3);
+        render(narrow_backend, window, &frame);
+        assert_eq!("main.rs [RO] [+]  Could not  Ln 1, Col 1", narrow_backend.row(2));
 
-    let cursor_position_info = get_cursor_position_info(cursor, absolute_cursor_position);
-    let print_column_start =
-        match terminal_width.checked_sub(cursor_position_info.chars().count() as u16) {
-            Some(x) => x,
-            None => 0,
+        let frame = RenderContext {
+            status: Status {
+                confirming_exit: true,
+                ..status
+            },
+            ..context(pt, cursor, config)
         };
-    queue!(
-        screen,
-        MoveTo(print_column_start, terminal_height - 1),
-        style::Print(cursor_position_info)
-    );
-
-    queue!(
-        screen,
-        MoveTo(relative_cursor_column, relative_cursor_row as u16),
-        Show
-    );
-    screen.flush().unwrap();
+        render(backend, window, &frame);
+
+        assert!(backend.row(2).starts_with("Save changes before exiting? (y/n/c) "));
+        assert_eq!(Some((37, 2)), backend.cursor());
+    }
+
+    #[test]
+    fn search_prompt_and_status() {
+        let pt = &PieceTable::new(String::from("abc"));
+        let backend = &mut MemoryBackend::new(40, 3);
+        let search = &mut Search::new(0);
+        search.query = String::from("xyz");
+        render_to(backend, pt, &Cursor::new(), Some(search));
+
+        assert_eq!(
+            "Find: xyz  (no matches)      Ln 1, Col 1",
+            backend.row(2)
+        );
+        assert_eq!(Some((9, 2)), backend.cursor());
+        assert_eq!(Color::Yellow, backend.cell(11, 2).style.foreground);
+    }
+
+    #[test]
+    fn soft_wrap_rows_and_cursor() {
+        let pt = &PieceTable::new(String::from("the quick brown fox\nend"));
+        let backend = &mut MemoryBackend::new(14, 4);
+        let window = &mut Window::new(0, 0, 0, 0);
+        let config = &mut config();
+        config.soft_wrap = true;
+
+        let cursor = &mut Cursor::new();
+        cursor.move_to_offset(pt, 12);
+        render(backend, window, &context(pt, cursor, config));
+
+        assert_eq!("  1 the quick ", backend.row(0));
+        assert_eq!("  ↪ brown fox ", backend.row(1));
+        assert_eq!("  2 end       ", backend.row(2));
+        assert_eq!(Some((6, 1)), backend.cursor());
+
+        // Scrolls by whole lines to keep the cursor in view
+        let backend = &mut MemoryBackend::new(14, 3);
+        cursor.move_to_offset(pt, 22);
+        render(backend, window, &context(pt, cursor, config));
+
+        assert_eq!("  2 end       ", backend.row(0));
+        assert_eq!(Some((6, 0)), backend.cursor());
+        assert_eq!(1, window.vertical_offset);
+    }
+
+    #[test]
+    fn positions_map_back_through_gutter_and_wrapping() {
+        let pt = &PieceTable::new(String::from("the quick brown fox\n\tend"));
+        let backend = &mut MemoryBackend::new(14, 4);
+        let window = &mut Window::new(0, 0, 0, 0);
+        let config = &mut config();
+        render(backend, window, &context(pt, &Cursor::new(), config));
+
+        assert_eq!((0, 0), position_at(pt, window, config, 0, 0));
+        assert_eq!((0, 2), position_at(pt, window, config, 6, 0));
+        // Within the tab, then past the end of the line and below the last line
+        assert_eq!((1, 0), position_at(pt, window, config, 6, 1));
+        assert_eq!((1, 4), position_at(pt, window, config, 13, 1));
+        assert_eq!((1, 4), position_at(pt, window, config, 6, 2));
+
+        config.soft_wrap = true;
+        render(backend, window, &context(pt, &Cursor::new(), config));
+
+        assert_eq!((0, 10), position_at(pt, window, config, 4, 1));
+        assert_eq!((1, 1), position_at(pt, window, config, 8, 2));
+    }
+
+    #[test]
+    fn cursor_hidden_when_scrolled_out_of_view() {
+        let pt = &PieceTable::new(String::from("a\nb\nc\nd"));
+        let backend = &mut MemoryBackend::new(12, 3);
+        let window = &mut Window::new(0, 0, 0, 0);
+        window.vertical_offset = 2;
+        window.follow_cursor = false;
+        render(backend, window, &context(pt, &Cursor::new(), &config()));
+
+        assert_eq!("  3 c       ", backend.row(0));
+        assert_eq!(None, backend.cursor());
+        assert_eq!(2, window.vertical_offset);
+    }
+
+    #[test]
+    fn small_terminals_hide_gutter_and_status_line() {
+        let pt = &PieceTable::new(String::from("ab\ncd"));
+        let window = &mut Window::new(0, 0, 0, 0);
+        let cursor = &mut Cursor::new();
+        cursor.move_to_offset(pt, 4);
+
+        let backend = &mut MemoryBackend::new(11, 3);
+        render(backend, window, &context(pt, cursor, &config()));
+        assert_eq!("ab         ", backend.row(0));
+        assert_eq!("cd         ", backend.row(1));
+        assert_eq!(Some((1, 1)), backend.cursor());
+        assert_eq!((1, 1), position_at(pt, window, &config(), 1, 1));
+
+        let backend = &mut MemoryBackend::new(3, 1);
+        render(backend, window, &context(pt, cursor, &config()));
+        assert_eq!("cd ", backend.row(0));
+        assert_eq!(Some((1, 0)), backend.cursor());
+
+        render(&mut MemoryBackend::new(0, 0), window, &context(pt, cursor, &config()));
+    }
 }
@@ -0,0 +1,215 @@
+use crate::text_buffer::TextBuffer;
+use regex::Regex;
+use std::ops::Range;
+
+/// Which part of an in-progress replace is capturing key input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReplaceStage {
+    /// Editing the regular expression to search for.
+    Pattern,
+    /// Editing the replacement text.
+    Replacement,
+    /// Stepping through matches, confirming or skipping each one.
+    Confirm,
+}
+
+/// A match of the pattern and the text it would be replaced with, capture groups expanded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Replacement {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// State of an in-progress find and replace.
+pub struct Replace {
+    pub pattern: String,
+    pub replacement: String,
+    pub stage: ReplaceStage,
+    /// `pattern` compiled, or the reason it failed to compile.
+    pub regex: Result<Regex, regex::Error>,
+    /// Offset searching for the next match starts from.
+    pub origin: usize,
+    /// Replacement the cursor was last moved to, if the pattern matched.
+    pub pending: Option<Replacement>,
+}
+
+impl Replace {
+    pub fn new(origin: usize) -> Replace {
+        Replace {
+            pattern: String::new(),
+            replacement: String::new(),
+            stage: ReplaceStage::Pattern,
+            regex: Regex::new(""),
+            origin,
+            pending: None,
+        }
+    }
+
+    /// Updates the pattern, recompiling it.
+    pub fn set_pattern(&mut self, pattern: String) {
+        self.regex = Regex::new(&pattern);
+        self.pattern = pattern;
+    }
+
+    /// Compiled pattern, or `None` if it is empty or invalid.
+    pub fn regex(&self) -> Option<&Regex> {
+        match (&self.regex, self.pattern.is_empty()) {
+            (Ok(regex), false) => Some(regex),
+            _ => None,
+        }
+    }
+}
+
+/// Replacement for the match in `captures`, taken from a line starting at `start_index`.
+fn replacement(captures: &regex::Captures, start_index: usize, replacement: &str) -> Replacement {
+    let found = captures.get(0).unwrap();
+    let mut text = String::new();
+    captures.expand(replacement, &mut text);
+    Replacement {
+        range: start_index + found.start()..start_index + found.end(),
+        text,
+    }
+}
+
+/// Replacements for every match of `regex` starting within `range`. Each `$n` or `${name}` in
+/// `replacement` is substituted with the corresponding capture group of the match.
+///
+/// Matching is done a line at a time, so that only one line is copied out of the text buffer at
+/// once, and matches do not span line breaks. The whole of each line is matched against, so that
+/// anchors such as `^` and `\b` are meaningful at the start of `range`.
+pub fn find_all(
+    text_buffer: &dyn TextBuffer,
+    regex: &Regex,
+    replacement: &str,
+    range: Range<usize>,
+) -> Vec<Replacement> {
+    let first_line = text_buffer.line_index_at(range.start);
+    let mut replacements = Vec::new();
+    for line_index in first_line..text_buffer.line_count() {
+        let line = text_buffer.line_at(line_index);
+        if line.start_index >= range.end && line_index > first_line {
+            break;
+        }
+        replacements.extend(
+            regex
+                .captures_iter(&line.content)
+                .map(|captures| self::replacement(&captures, line.start_index, replacement))
+                .filter(|r| range.contains(&r.range.start)),
+        );
+    }
+    replacements
+}
+
+/// Replacement for the first match of `regex` starting at or after `from`. Unlike a search, this
+/// does not wrap around, so that replacing text which matches again cannot continue forever.
+/// As with `find_all`, matching is done a line at a time.
+pub fn find_next(
+    text_buffer: &dyn TextBuffer,
+    regex: &Regex,
+    replacement: &str,
+    from: usize,
+) -> Option<Replacement> {
+    let first_line = text_buffer.line_index_at(from);
+    (first_line..text_buffer.line_count()).find_map(|line_index| {
+        let line = text_buffer.line_at(line_index);
+        // The start of the line before `from` is kept as context for anchors
+        let start = std::cmp::min(from.saturating_sub(line.start_index), line.len());
+        regex
+            .captures_at(&line.content, start)
+            .map(|captures| self::replacement(&captures, line.start_index, replacement))
+    })
+}
+
+/// Applies `replacements`, which must be in document order and not overlap, as a single revision.
+pub fn apply(text_buffer: &mut dyn TextBuffer, replacements: &[Replacement]) {
+    text_buffer.begin_revision();
+    for replacement in replacements.iter().rev() {
+        let start = replacement.range.start;
+        text_buffer.remove(replacement.range.clone());
+        text_buffer.insert(&replacement.text, start);
+    }
+    text_buffer.end_revision();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_buffer::piece_table::PieceTable;
+
+    #[test]
+    fn find_all_expands_capture_groups() {
+        let pt = &mut PieceTable::new(String::from("key = value\nname = rstext"));
+        pt.insert("x", 0);
+
+        // xkey = value\nname = rstext
+        let regex = Regex::new(r"(\w+) = (?P<value>\w+)").unwrap();
+        let expected = vec![
+            Replacement {
+                range: 0..12,
+                text: String::from("value: xkey"),
+            },
+            Replacement {
+                range: 13..26,
+                text: String::from("rstext: name"),
+            },
+        ];
+        assert_eq!(expected, find_all(pt, &regex, "${value}: $1", 0..26));
+    }
+
+    #[test]
+    fn find_next_does_not_wrap() {
+        let pt = &PieceTable::new(String::from("ab ab ab"));
+        let regex = Regex::new("ab").unwrap();
+
+        assert_eq!(Some(3..5), find_next(pt, &regex, "", 1).map(|r| r.range));
+        assert_eq!(None, find_next(pt, &regex, "", 7));
+        assert_eq!(None, find_next(pt, &regex, "", 20));
+    }
+
+    #[test]
+    fn find_next_matches_anchors_against_preceding_text() {
+        let pt = &PieceTable::new(String::from("one two\nthree"));
+
+        let regex = Regex::new(r"\b\w").unwrap();
+        assert_eq!(Some(4..5), find_next(pt, &regex, "", 1).map(|r| r.range));
+        let regex = Regex::new(r"(?m)^(\w+)").unwrap();
+        let expected = Replacement {
+            range: 8..13,
+            text: String::from("<three>"),
+        };
+        assert_eq!(Some(expected), find_next(pt, &regex, "<$1>", 1));
+
+        // A range starting inside a word does not make a word boundary there
+        let regex = Regex::new(r"\b\w+").unwrap();
+        let ranges = find_all(pt, &regex, "", 5..13).into_iter().map(|r| r.range);
+        assert_eq!(vec![8..13], ranges.collect::<Vec<Range<usize>>>());
+    }
+
+    #[test]
+    fn apply_is_single_revision() {
+        let pt = &mut PieceTable::new(String::from("one two three"));
+        let regex = Regex::new(r"\w+").unwrap();
+
+        let replacements = find_all(pt, &regex, "<$0>", 0..pt.length);
+        apply(pt, &replacements);
+        assert_eq!("<one> <two> <three>", pt.all_content());
+
+        pt.undo();
+        assert_eq!("one two three", pt.all_content());
+        pt.redo();
+        assert_eq!("<one> <two> <three>", pt.all_content());
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let replace = &mut Replace::new(0);
+        assert_eq!(None, replace.regex().map(|r| r.as_str()));
+
+        replace.set_pattern(String::from("(a"));
+        assert!(replace.regex.is_err());
+        assert_eq!(None, replace.regex().map(|r| r.as_str()));
+
+        replace.set_pattern(String::from("(a)"));
+        assert_eq!(Some("(a)"), replace.regex().map(|r| r.as_str()));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::text_buffer::TextBuffer;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// State of an in-progress incremental search.
+pub struct Search {
+    pub query: String,
+    pub case_sensitive: bool,
+    /// Offset searching starts from as the query is edited.
+    pub origin: usize,
+    /// Range of the match the cursor was last moved to, if the query matched.
+    pub current_match: Option<Range<usize>>,
+}
+
+impl Search {
+    pub fn new(origin: usize) -> Search {
+        Search {
+            query: String::new(),
+            case_sensitive: false,
+            origin,
+            current_match: None,
+        }
+    }
+}
+
+fn normalize(c: char, case_sensitive: bool) -> char {
+    match case_sensitive {
+        true => c,
+        false => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+/// Knuth-Morris-Pratt failure function: length of the longest proper prefix of
+/// `pattern[..=i]` which is also a suffix of it.
+fn prefix_table(pattern: &[char]) -> Vec<usize> {
+    let mut table = vec![0; pattern.len()];
+    let mut matched = 0;
+    for i in 1..pattern.len() {
+        while matched > 0 && pattern[i] != pattern[matched] {
+            matched = table[matched - 1];
+        }
+        if pattern[i] == pattern[matched] {
+            matched += 1;
+        }
+        table[i] = matched;
+    }
+
+    table
+}
+
+/// Non-overlapping matches of a pattern, found by streaming over text buffer chunks.
+pub struct Matches<'a> {
+    chunks: Box<dyn Iterator<Item = &'a str> + 'a>,
+    chunk: &'a str,
+    /// Absolute offset of the start of `chunk`.
+    chunk_start: usize,
+    /// Offset of the next character to examine within `chunk`.
+    chunk_offset: usize,
+    pattern: Vec<char>,
+    table: Vec<usize>,
+    case_sensitive: bool,
+    /// Number of pattern characters currently matched.
+    matched: usize,
+    /// Start offsets of the most recently examined characters, at most one per pattern character.
+    starts: VecDeque<usize>,
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+
+        loop {
+            let c = match self.chunk[self.chunk_offset..].chars().next() {
+                Some(c) => c,
+                None => {
+                    self.chunk_start += self.chunk.len();
+                    self.chunk = self.chunks.next()?;
+                    self.chunk_offset = 0;
+                    continue;
+                }
+            };
+
+            let start = self.chunk_start + self.chunk_offset;
+            self.chunk_offset += c.len_utf8();
+            if self.starts.len() == self.pattern.len() {
+                self.starts.pop_front();
+            }
+            self.starts.push_back(start);
+
+            let c = normalize(c, self.case_sensitive);
+            while self.matched > 0 && c != self.pattern[self.matched] {
+                self.matched = self.table[self.matched - 1];
+            }
+            if c == self.pattern[self.matched] {
+                self.matched += 1;
+            }
+
+            if self.matched == self.pattern.len() {
+                let match_start = self.starts[self.starts.len() - self.pattern.len()];
+                self.matched = 0;
+                self.starts.clear();
+                return Some(match_start..start + c.len_utf8());
+            }
+        }
+    }
+}
+
+/// Matches of `pattern` starting at or after `from`.
+pub fn matches<'a>(
+    text_buffer: &'a dyn TextBuffer,
+    pattern: &str,
+    case_sensitive: bool,
+    from: usize,
+) -> Matches<'a> {
+    let pattern = pattern
+        .chars()
+        .map(|c| normalize(c, case_sensitive))
+        .collect::<Vec<char>>();
+    let table = prefix_table(&pattern);
+
+    Matches {
+        chunks: text_buffer.chunks(from..usize::MAX),
+        chunk: "",
+        chunk_start: from,
+        chunk_offset: 0,
+        starts: VecDeque::with_capacity(pattern.len()),
+        pattern,
+        table,
+        case_sensitive,
+        matched: 0,
+    }
+}
+
+/// Matches of `pattern` starting within `range`.
+pub fn find_all(
+    text_buffer: &dyn TextBuffer,
+    pattern: &str,
+    case_sensitive: bool,
+    range: Range<usize>,
+) -> Vec<Range<usize>> {
+    matches(text_buffer, pattern, case_sensitive, range.start)
+        .take_while(|m| m.start < range.end)
+        .collect()
+}
+
+/// First match starting at or after `from`, wrapping around to the start of the buffer.
+pub fn find_next(
+    text_buffer: &dyn TextBuffer,
+    pattern: &str,
+    case_sensitive: bool,
+    from: usize,
+) -> Option<Range<usize>> {
+    matches(text_buffer, pattern, case_sensitive, from)
+        .next()
+        .or_else(|| matches(text_buffer, pattern, case_sensitive, 0).next())
+}
+
+/// Last match ending at or before `to`, wrapping around to the end of the buffer.
+pub fn find_previous(
+    text_buffer: &dyn TextBuffer,
+    pattern: &str,
+    case_sensitive: bool,
+    to: usize,
+) -> Option<Range<usize>> {
+    matches(text_buffer, pattern, case_sensitive, 0)
+        .take_while(|m| m.end <= to)
+        .last()
+        .or_else(|| matches(text_buffer, pattern, case_sensitive, to).last())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_buffer::piece_table::PieceTable;
+
+    #[test]
+    fn prefix_table_correct() {
+        let pattern = "abacab".chars().collect::<Vec<char>>();
+        assert_eq!(vec![0, 0, 1, 0, 1, 2], prefix_table(&pattern));
+    }
+
+    #[test]
+    fn matches_across_pieces() {
+        let pt = &mut PieceTable::new(String::from("foo bar baz"));
+        pt.insert("ba", 0);
+        pt.insert("r", 2);
+
+        // barfoo bar baz
+        let found = matches(pt, "bar", true, 0).collect::<Vec<Range<usize>>>();
+        assert_eq!(vec![0..3, 7..10], found);
+
+        let found = matches(pt, "oo ba", true, 4).collect::<Vec<Range<usize>>>();
+        assert_eq!(vec![4..9], found);
+    }
+
+    #[test]
+    fn matches_case_insensitive() {
+        let pt = &PieceTable::new(String::from("Foo fOO ÉCOLE école"));
+
+        let found = matches(pt, "foo", false, 0).collect::<Vec<Range<usize>>>();
+        assert_eq!(vec![0..3, 4..7], found);
+
+        let found = matches(pt, "foo", true, 0).collect::<Vec<Range<usize>>>();
+        assert_eq!(Vec::<Range<usize>>::new(), found);
+
+        let found = matches(pt, "école", false, 0).collect::<Vec<Range<usize>>>();
+        assert_eq!(vec![8..14, 15..21], found);
+    }
+
+    #[test]
+    fn matches_after_partial_match() {
+        let pt = &PieceTable::new(String::from("aaab aab"));
+
+        let found = matches(pt, "aab", true, 0).collect::<Vec<Range<usize>>>();
+        assert_eq!(vec![1..4, 5..8], found);
+    }
+
+    #[test]
+    fn find_next_and_previous_wrap() {
+        let pt = &PieceTable::new(String::from("ab ab ab"));
+
+        assert_eq!(Some(3..5), find_next(pt, "ab", true, 1));
+        assert_eq!(Some(0..2), find_next(pt, "ab", true, 7));
+        assert_eq!(Some(3..5), find_previous(pt, "ab", true, 6));
+        assert_eq!(Some(6..8), find_previous(pt, "ab", true, 1));
+        assert_eq!(vec![3..5], find_all(pt, "ab", true, 1..6));
+    }
+}
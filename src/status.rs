@@ -0,0 +1,29 @@
+use crate::file::LineEnding;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageKind {
+    Info,
+    Error,
+}
+
+/// Message reported by a command, shown in the status bar until it times out.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusMessage {
+    pub kind: MessageKind,
+    pub text: String,
+}
+
+/// What the status bar shows about the file being edited, alongside the cursor position.
+#[derive(Clone, Debug, Default)]
+pub struct Status<'a> {
+    /// Name of the file, if it has one.
+    pub file_name: Option<&'a str>,
+    /// Whether there are changes which have not been saved.
+    pub modified: bool,
+    /// Whether the file cannot be saved.
+    pub read_only: bool,
+    /// Whether exiting is waiting on the choice of what to do with unsaved changes.
+    pub confirming_exit: bool,
+    pub line_ending: LineEnding,
+    pub message: Option<&'a StatusMessage>,
+}
@@ -1,3 +1,6 @@
+use std::ops::Range;
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
 pub fn line_break_offsets(s: &str) -> Vec<usize> {
     s.bytes()
         .enumerate()
@@ -25,6 +28,157 @@ pub fn prev_char_idx(s: &str, byte_offset: usize) -> Option<usize> {
         .map(|(i, _)| i)
 }
 
+/// Byte offset of the end of the grapheme cluster starting at `byte_offset`.
+pub fn next_grapheme_idx(s: &str, byte_offset: usize) -> Option<usize> {
+    GraphemeCursor::new(byte_offset, s.len(), true)
+        .next_boundary(s, 0)
+        .unwrap_or(None)
+}
+
+/// Byte offset of the start of the grapheme cluster ending at `byte_offset`.
+pub fn prev_grapheme_idx(s: &str, byte_offset: usize) -> Option<usize> {
+    GraphemeCursor::new(byte_offset, s.len(), true)
+        .prev_boundary(s, 0)
+        .unwrap_or(None)
+}
+
+/// Number of extended grapheme clusters in `s`.
+pub fn grapheme_count(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// Classes of characters words are made up of. Word boundaries lie wherever the class changes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CharClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+/// Class of `c`, where letters, digits and any of `word_characters` make up words.
+pub fn char_class(c: char, word_characters: &str) -> CharClass {
+    match c {
+        c if c.is_whitespace() => CharClass::Whitespace,
+        c if c.is_alphanumeric() || word_characters.contains(c) => CharClass::Word,
+        _ => CharClass::Punctuation,
+    }
+}
+
+fn grapheme_class(grapheme: &str, word_characters: &str) -> CharClass {
+    grapheme
+        .chars()
+        .next()
+        .map_or(CharClass::Whitespace, |c| char_class(c, word_characters))
+}
+
+/// Byte offset of the end of the word at or after `byte_offset`, skipping any whitespace before it.
+pub fn next_word_boundary(s: &str, byte_offset: usize, word_characters: &str) -> usize {
+    let mut graphemes = s[byte_offset..]
+        .grapheme_indices(true)
+        .map(|(i, g)| (byte_offset + i, grapheme_class(g, word_characters)))
+        .skip_while(|(_, class)| *class == CharClass::Whitespace);
+    let word_class = match graphemes.next() {
+        Some((_, class)) => class,
+        None => return s.len(),
+    };
+
+    graphemes
+        .find(|(_, class)| *class != word_class)
+        .map_or(s.len(), |(i, _)| i)
+}
+
+/// Byte offset of the start of the word before `byte_offset`, skipping any whitespace after it.
+pub fn prev_word_boundary(s: &str, byte_offset: usize, word_characters: &str) -> usize {
+    let mut graphemes = s[..byte_offset]
+        .grapheme_indices(true)
+        .rev()
+        .map(|(i, g)| (i, grapheme_class(g, word_characters)))
+        .skip_while(|(_, class)| *class == CharClass::Whitespace);
+    let (mut start, word_class) = match graphemes.next() {
+        Some(first) => first,
+        None => return 0,
+    };
+
+    for (i, class) in graphemes {
+        if class != word_class {
+            break;
+        }
+        start = i;
+    }
+    start
+}
+
+/// Byte range of the word, run of punctuation or run of whitespace containing the grapheme at
+/// `byte_offset` or, at the end of `s`, the grapheme before it.
+pub fn word_at(s: &str, byte_offset: usize, word_characters: &str) -> Range<usize> {
+    let grapheme = s[byte_offset..]
+        .graphemes(true)
+        .next()
+        .or_else(|| s[..byte_offset].graphemes(true).next_back());
+    let class = match grapheme {
+        Some(g) => grapheme_class(g, word_characters),
+        None => return byte_offset..byte_offset,
+    };
+
+    let start = s[..byte_offset]
+        .grapheme_indices(true)
+        .rev()
+        .take_while(|(_, g)| grapheme_class(g, word_characters) == class)
+        .last()
+        .map_or(byte_offset, |(i, _)| i);
+    let end = s[byte_offset..]
+        .grapheme_indices(true)
+        .find(|(_, g)| grapheme_class(g, word_characters) != class)
+        .map_or(s.len(), |(i, _)| byte_offset + i);
+    start..end
+}
+
+/// Offset of the bracket matching the first of `forward` or, failing that, the first of
+/// `backward`. `forward` runs from a position through the characters after it, and `backward`
+/// from that same position through the characters before it in reverse, each character paired
+/// with its offset.
+pub fn matching_bracket_around(
+    forward: impl Iterator<Item = (usize, char)>,
+    backward: impl Iterator<Item = (usize, char)>,
+) -> Option<usize> {
+    const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+    let mut forward = forward.peekable();
+    let mut backward = backward.peekable();
+    let is_bracket = |c: char| PAIRS.iter().any(|(open, close)| c == *open || c == *close);
+    let (offset, bracket) = match forward.peek() {
+        Some((offset, c)) if is_bracket(*c) => (*offset, *c),
+        _ => *backward.peek().filter(|(_, c)| is_bracket(*c))?,
+    };
+
+    let mut depth = 0usize;
+    let mut step = |c: char, same: char, other: char| {
+        if c == same {
+            depth += 1;
+        } else if c == other {
+            depth -= 1;
+        }
+        depth == 0
+    };
+    // A bracket found on one side is stepped over before scanning the other side for its match
+    if let Some((open, close)) = PAIRS.iter().find(|(open, _)| *open == bracket) {
+        backward
+            .next_if(|(i, _)| *i == offset)
+            .into_iter()
+            .chain(forward)
+            .find(|(_, c)| step(*c, *open, *close))
+            .map(|(i, _)| i)
+    } else {
+        let (open, close) = PAIRS.iter().find(|(_, close)| *close == bracket)?;
+        forward
+            .next_if(|(i, _)| *i == offset)
+            .into_iter()
+            .chain(backward)
+            .find(|(_, c)| step(*c, *close, *open))
+            .map(|(i, _)| i)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,6 +215,52 @@ mod tests {
         assert_eq!(idx, Some(2));
     }
 
+    #[test]
+    fn grapheme_idx_steps_by_cluster() {
+        // e + combining acute, family emoji joined by ZWJs, CJK character
+        let s = String::from("e\u{301}👨‍👩‍👧中");
+        assert_eq!(next_grapheme_idx(&s, 0), Some(3));
+        assert_eq!(next_grapheme_idx(&s, 3), Some(21));
+        assert_eq!(next_grapheme_idx(&s, 21), Some(24));
+        assert_eq!(next_grapheme_idx(&s, 24), None);
+        assert_eq!(prev_grapheme_idx(&s, 24), Some(21));
+        assert_eq!(prev_grapheme_idx(&s, 21), Some(3));
+        assert_eq!(prev_grapheme_idx(&s, 3), Some(0));
+        assert_eq!(prev_grapheme_idx(&s, 0), None);
+        assert_eq!(grapheme_count(&s), 3);
+    }
+
+    #[test]
+    fn word_boundaries_correct() {
+        let s = String::from("let foo_bar = a.b(1);");
+        assert_eq!(next_word_boundary(&s, 0, "_"), 3);
+        assert_eq!(next_word_boundary(&s, 3, "_"), 11);
+        assert_eq!(next_word_boundary(&s, 11, "_"), 13);
+        assert_eq!(next_word_boundary(&s, 3, ""), 7);
+        assert_eq!(next_word_boundary(&s, 17, "_"), 18);
+        assert_eq!(next_word_boundary(&s, 19, "_"), 21);
+        assert_eq!(next_word_boundary(&s, 21, "_"), 21);
+
+        assert_eq!(prev_word_boundary(&s, 11, "_"), 4);
+        assert_eq!(prev_word_boundary(&s, 12, "_"), 4);
+        assert_eq!(prev_word_boundary(&s, 11, ""), 8);
+        assert_eq!(prev_word_boundary(&s, 3, "_"), 0);
+        assert_eq!(prev_word_boundary(&s, 0, "_"), 0);
+    }
+
+    #[test]
+    fn word_at_correct() {
+        let s = String::from("let foo_bar  = a.b(1);");
+        assert_eq!(word_at(&s, 0, "_"), 0..3);
+        assert_eq!(word_at(&s, 6, "_"), 4..11);
+        assert_eq!(word_at(&s, 6, ""), 4..7);
+        assert_eq!(word_at(&s, 11, "_"), 11..13);
+        assert_eq!(word_at(&s, 13, "_"), 13..14);
+        assert_eq!(word_at(&s, 20, "_"), 20..22);
+        assert_eq!(word_at(&s, 22, "_"), 20..22);
+        assert_eq!(word_at("", 0, "_"), 0..0);
+    }
+
     #[test]
     fn prev_char_idx_start_correct() {
         let s = String::from("abcdef");
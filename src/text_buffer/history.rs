@@ -0,0 +1,168 @@
+use crate::text_buffer::piece::Piece;
+
+/// A single splice of the piece sequence: `removed` pieces were replaced by `inserted` pieces
+/// starting at byte `offset`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Change {
+    pub offset: usize,
+    pub removed: Vec<Piece>,
+    pub inserted: Vec<Piece>,
+}
+
+impl Change {
+    pub fn removed_len(&self) -> usize {
+        self.removed.iter().map(|p| p.length).sum()
+    }
+
+    pub fn inserted_len(&self) -> usize {
+        self.inserted.iter().map(|p| p.length).sum()
+    }
+
+    /// Attempts to fold `next` into this change, returning whether it was merged.
+    /// Only runs of typing (insert directly after the previous insert), backspacing (remove
+    /// directly before the previous remove) and forward deleting (remove at the offset of the
+    /// previous remove) are merged.
+    fn merge(&mut self, next: &Change) -> bool {
+        if self.removed.is_empty()
+            && next.removed.is_empty()
+            && next.offset == self.offset + self.inserted_len()
+        {
+            for piece in next.inserted.iter() {
+                push_piece(&mut self.inserted, piece.clone());
+            }
+            return true;
+        }
+
+        if self.inserted.is_empty()
+            && next.inserted.is_empty()
+            && next.offset + next.removed_len() == self.offset
+        {
+            let mut removed = Vec::new();
+            for piece in next.removed.iter().chain(self.removed.iter()) {
+                push_piece(&mut removed, piece.clone());
+            }
+            self.removed = removed;
+            self.offset = next.offset;
+            return true;
+        }
+
+        if self.inserted.is_empty() && next.inserted.is_empty() && next.offset == self.offset {
+            for piece in next.removed.iter() {
+                push_piece(&mut self.removed, piece.clone());
+            }
+            return true;
+        }
+
+        false
+    }
+}
+
+fn push_piece(pieces: &mut Vec<Piece>, piece: Piece) {
+    let joined = pieces.last().and_then(|last| last.join(&piece));
+    match joined {
+        Some(joined) => *pieces.last_mut().unwrap() = joined,
+        None => pieces.push(piece),
+    }
+}
+
+pub struct History {
+    undo_stack: Vec<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+    /// Nesting depth of open revisions; changes recorded while non-zero form a single revision.
+    revision_depth: usize,
+    /// Whether the open revision has been pushed onto the undo stack yet.
+    revision_started: bool,
+    /// Number of revisions on the undo stack when the content was last saved, or `None` if that
+    /// state can no longer be reached by undoing or redoing.
+    saved_depth: Option<usize>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            revision_depth: 0,
+            revision_started: false,
+            saved_depth: Some(0),
+        }
+    }
+
+    /// Marks the current state as the one last saved.
+    pub fn mark_saved(&mut self) {
+        self.saved_depth = Some(self.undo_stack.len());
+    }
+
+    /// Whether undoing and redoing has returned to the state last saved.
+    pub fn is_saved(&self) -> bool {
+        self.saved_depth == Some(self.undo_stack.len())
+    }
+
+    pub fn begin_revision(&mut self) {
+        if self.revision_depth == 0 {
+            self.revision_started = false;
+        }
+        self.revision_depth += 1;
+    }
+
+    pub fn end_revision(&mut self) {
+        self.revision_depth = self.revision_depth.saturating_sub(1);
+    }
+
+    /// Records a change, folding it into the most recent revision when `coalesce` is set and
+    /// the two changes are contiguous. Any undone revisions are discarded.
+    pub fn record(&mut self, change: Change, coalesce: bool) {
+        self.redo_stack.clear();
+        if matches!(self.saved_depth, Some(depth) if depth > self.undo_stack.len()) {
+            // The saved state was among the undone revisions just discarded
+            self.saved_depth = None;
+        }
+        // Folding a change into the saved revision would leave no way to undo back to it
+        let coalesce = coalesce && !self.is_saved();
+
+        if self.revision_depth > 0 {
+            if self.revision_started {
+                let revision = self.undo_stack.last_mut().unwrap();
+                let merged = coalesce && revision.last_mut().is_some_and(|c| c.merge(&change));
+                if !merged {
+                    revision.push(change);
+                }
+            } else {
+                self.revision_started = true;
+                self.undo_stack.push(vec![change]);
+            }
+            return;
+        }
+
+        if coalesce {
+            let last_change = self.undo_stack.last_mut().and_then(|r| r.last_mut());
+            if let Some(last_change) = last_change {
+                if last_change.merge(&change) {
+                    return;
+                }
+            }
+        }
+
+        self.undo_stack.push(vec![change]);
+    }
+
+    /// Pops the most recent revision for reverting, moving it onto the redo stack.
+    pub fn undo(&mut self) -> Option<Vec<Change>> {
+        let revision = self.undo_stack.pop()?;
+        self.redo_stack.push(revision.clone());
+        Some(revision)
+    }
+
+    /// Pops the most recently undone revision for reapplying, moving it back onto the undo stack.
+    pub fn redo(&mut self) -> Option<Vec<Change>> {
+        let revision = self.redo_stack.pop()?;
+        self.undo_stack.push(revision.clone());
+        Some(revision)
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new()
+    }
+}
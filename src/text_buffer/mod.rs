@@ -1,6 +1,8 @@
+pub mod history;
 pub mod line;
 pub mod piece;
 pub mod piece_table;
+pub mod piece_tree;
 
 use line::Line;
 use std::ops::Range;
@@ -8,7 +10,23 @@ use std::ops::Range;
 pub trait TextBuffer {
     fn insert(&mut self, s: &str, offset: usize);
     fn all_content(&self) -> String;
+    /// Contents of `range` as a sequence of string slices, without copying.
+    fn chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn Iterator<Item = &'a str> + 'a>;
     fn line_at(&self, idx: usize) -> Line;
+    /// Index of the line containing byte `offset`.
+    fn line_index_at(&self, offset: usize) -> usize;
     fn line_count(&self) -> usize;
     fn remove(&mut self, range: Range<usize>);
+    /// Starts grouping edits into a single revision, until the matching `end_revision`.
+    fn begin_revision(&mut self);
+    fn end_revision(&mut self);
+    /// Reverts the most recent revision, returning the byte offset the cursor should move to.
+    fn undo(&mut self) -> Option<usize>;
+    /// Reapplies the most recently undone revision, returning the byte offset the cursor should move to.
+    fn redo(&mut self) -> Option<usize>;
+    /// Marks the current content as saved.
+    fn mark_saved(&mut self);
+    /// Whether the content differs from when it was last saved, or created if never saved.
+    /// Undoing or redoing back to the saved content counts as unmodified.
+    fn is_modified(&self) -> bool;
 }
@@ -6,7 +6,7 @@ pub enum Buffer {
     Original
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Piece {
     /// Associated PieceTable buffer.
     pub buffer: Buffer,
@@ -39,6 +39,27 @@ impl Piece {
         }
     }
 
+    /// Joins `other` onto the end of this piece if it continues the same buffer region.
+    pub fn join(&self, other: &Piece) -> Option<Piece> {
+        if self.buffer != other.buffer || self.start + self.length != other.start {
+            return None;
+        }
+
+        let line_break_offsets = self
+            .line_break_offsets
+            .iter()
+            .map(|x| *x)
+            .chain(other.line_break_offsets.iter().map(|x| x + self.length))
+            .collect();
+
+        Some(Piece {
+            buffer: self.buffer,
+            start: self.start,
+            length: self.length + other.length,
+            line_break_offsets
+        })
+    }
+
     pub fn split_at(&self, offset: usize) -> (Self, Self) {
         let left_line_break_offsets = self
             .line_break_offsets
@@ -127,6 +148,35 @@ mod tests {
         assert_eq!(expected, original.extend("a\nb"));
     }
 
+    #[test]
+    fn join()
+    {
+        let left = Piece
+        {
+            buffer: Buffer::Added,
+            start: 3,
+            length: 4,
+            line_break_offsets: vec![1]
+        };
+        let right = Piece
+        {
+            buffer: Buffer::Added,
+            start: 7,
+            length: 3,
+            line_break_offsets: vec![0, 2]
+        };
+
+        let expected = Piece
+        {
+            buffer: Buffer::Added,
+            start: 3,
+            length: 7,
+            line_break_offsets: vec![1, 4, 6]
+        };
+        assert_eq!(Some(expected), left.join(&right));
+        assert_eq!(None, right.join(&left));
+    }
+
     #[test]
     fn split_at()
     {
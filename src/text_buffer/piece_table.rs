@@ -1,6 +1,8 @@
 use crate::str_utils;
 use crate::text_buffer::{Line, TextBuffer};
+use crate::text_buffer::history::{Change, History};
 use crate::text_buffer::piece::{ Buffer, Piece};
+use crate::text_buffer::piece_tree::{self, PieceTree};
 use std::iter::Iterator;
 use std::ops::{Index, Range};
 
@@ -18,21 +20,24 @@ struct ChangeRecord {
 pub struct PieceTable {
     original: String,
     added: String,
-    pieces: Vec<Piece>,
+    pieces: PieceTree,
     pub length: usize,
     last_insert: Option<ChangeRecord>,
-    last_remove: Option<ChangeRecord>,
+    /// Offset of the most recent removal, while no other edit has followed it.
+    last_remove: Option<usize>,
+    history: History,
 }
 
 impl PieceTable {
     pub fn new(content: String) -> Self {
         let mut pt = Self {
             length: content.len(),
-            pieces: Vec::new(),
+            pieces: PieceTree::new(),
             original: content,
             added: String::new(),
             last_insert: None,
             last_remove: None,
+            history: History::new(),
         };
         if !pt.original.is_empty() {
             pt.pieces.push(pt.create_piece(Buffer::Original, 0, pt.length));
@@ -59,64 +64,78 @@ impl PieceTable {
         piece
     }
 
-    fn iter(&self) -> PieceTableIter {
-        PieceTableIter {
-            inner: self,
-            current_piece_index: 0,
-            current_piece_offset: 0,
-            end_piece_index: self.pieces.len(),
-            end_piece_offset: 0,
-        }
+    fn piece(&self, piece_index: usize) -> &Piece {
+        self.pieces.get(piece_index).expect("Piece index out of range")
     }
 
-    fn iter_range(&self, range: Range<usize>) -> PieceTableIter {
-        if self.length == 0 || range.start >= range.end {
-            return PieceTableIter {
-                inner: self,
-                current_piece_index: 0,
-                current_piece_offset: 0,
-                end_piece_index: 0,
-                end_piece_offset: 0,
-            };
-        }
-
-        let start_location = self.offset_to_piece_position(range.start);
-        let end_location = self.offset_to_piece_position(range.end.checked_sub(1).unwrap_or(0));
-
-        let (start_piece_index, start_piece_offset) = match start_location {
-            PiecePosition::Head(piece_index) => (piece_index, 0),
-            PiecePosition::Body(piece_index, piece_offset) => (piece_index, piece_offset),
-            PiecePosition::EOF => panic!("Start index out of range"),
-        };
+    fn iter(&self) -> PieceTableIter<'_> {
+        self.iter_range(0..self.pieces.length())
+    }
 
-        let (end_piece_index, end_piece_offset) = match end_location {
-            PiecePosition::Head(piece_index) => (piece_index, 1),
-            PiecePosition::Body(piece_index, piece_offset) => (piece_index, piece_offset + 1),
-            PiecePosition::EOF => (self.pieces.len(), 0),
+    fn iter_range(&self, range: Range<usize>) -> PieceTableIter<'_> {
+        let end = std::cmp::min(range.end, self.pieces.length());
+        let location = match range.start < end {
+            true => self.pieces.find_by_offset(range.start),
+            false => None,
         };
 
-        PieceTableIter {
-            inner: self,
-            current_piece_index: start_piece_index,
-            current_piece_offset: start_piece_offset,
-            end_piece_index,
-            end_piece_offset,
+        match location {
+            Some(location) => {
+                let mut pieces = self.pieces.iter_at(location.index);
+                PieceTableIter {
+                    inner: self,
+                    current_piece: pieces.next(),
+                    pieces,
+                    current_piece_offset: range.start - location.start,
+                    remaining: end - range.start,
+                }
+            }
+            None => PieceTableIter {
+                inner: self,
+                current_piece: None,
+                pieces: self.pieces.iter_at(self.pieces.len()),
+                current_piece_offset: 0,
+                remaining: 0,
+            },
         }
     }
 
     fn offset_to_piece_position(&self, offset: usize) -> PiecePosition {
-        let mut item_count = 0usize;
-        for (piece_index, piece) in self.pieces.iter().enumerate() {
-            if offset >= item_count && offset < item_count + piece.length {
-                return match offset {
-                    offset if offset == item_count => PiecePosition::Head(piece_index),
-                    _ => PiecePosition::Body(piece_index, offset - item_count),
-                };
+        match self.pieces.find_by_offset(offset) {
+            Some(location) if location.start == offset => PiecePosition::Head(location.index),
+            Some(location) => PiecePosition::Body(location.index, offset - location.start),
+            None => PiecePosition::EOF,
+        }
+    }
+
+    /// Ensures a piece boundary exists at `offset`, returning the index of the piece starting there.
+    fn split_pieces_at(&mut self, offset: usize) -> usize {
+        match self.offset_to_piece_position(offset) {
+            PiecePosition::Head(piece_index) => piece_index,
+            PiecePosition::Body(piece_index, piece_offset) => {
+                let (left, right) = self.piece(piece_index).split_at(piece_offset);
+                self.pieces.replace(piece_index, left);
+                self.pieces.insert(piece_index + 1, right);
+                piece_index + 1
             }
-            item_count += piece.length;
+            PiecePosition::EOF => self.pieces.len(),
         }
+    }
+
+    /// Replaces `len` bytes at `offset` with `pieces`, bypassing history.
+    /// At most two pieces are split (at either end of the replaced region); the pieces between
+    /// are removed in a single operation and returned.
+    fn splice(&mut self, offset: usize, len: usize, pieces: Vec<Piece>) -> Vec<Piece> {
+        let inserted_len: usize = pieces.iter().map(|p| p.length).sum();
+        let start = self.split_pieces_at(offset);
+        let end = self.split_pieces_at(offset + len);
+        let removed = self.pieces.splice(start..end, pieces);
+
+        self.length = self.length - len + inserted_len;
+        self.last_insert = None;
+        self.last_remove = None;
 
-        PiecePosition::EOF
+        removed
     }
 
     fn raw_insert(&mut self, s: &str, offset: usize) {
@@ -135,9 +154,9 @@ impl PieceTable {
                     piece_index,
                 });
             }
-            PiecePosition::Body(piece_index, offset) => {
-                let (left, right) = self.pieces[piece_index].split_at(offset);
-                self.pieces[piece_index] = left;
+            PiecePosition::Body(piece_index, piece_offset) => {
+                let (left, right) = self.piece(piece_index).split_at(piece_offset);
+                self.pieces.replace(piece_index, left);
                 self.pieces.insert(piece_index + 1, new_piece);
                 self.pieces.insert(piece_index + 2, right);
                 self.last_insert = Some(ChangeRecord {
@@ -154,37 +173,6 @@ impl PieceTable {
             },
         }
     }
-
-    fn raw_remove(&mut self, offset: usize) {
-        let location = self.offset_to_piece_position(offset);
-        let cached_piece_index: Option<usize> = match location {
-            PiecePosition::Head(piece_index) => {
-                let original_piece = &self.pieces[piece_index];
-
-                if original_piece.length <= 1 {
-                    self.pieces.remove(piece_index);
-                    piece_index.checked_sub(1)
-                } else {
-                    self.pieces[piece_index] = original_piece.truncate_left(1);
-                    None
-                }
-            }
-            PiecePosition::Body(piece_index, piece_offset) => {
-                let (left, right) = self.pieces[piece_index].split_at(piece_offset);
-                self.pieces[piece_index] = left;
-                self.pieces.insert(piece_index + 1, right.truncate_left(1));
-                Some(piece_index)
-            }
-            PiecePosition::EOF => panic!("Attempted to remove from EOF"),
-        };
-
-        self.last_remove = cached_piece_index.map(|i| {
-            ChangeRecord {
-                offset: offset.checked_sub(1).unwrap_or(0),
-                piece_index: i,
-            }
-        });
-    }
 }
 
 impl TextBuffer for PieceTable {
@@ -193,9 +181,22 @@ impl TextBuffer for PieceTable {
         self.length += to_insert.len();
         self.last_remove = None;
 
+        let change = Change {
+            offset,
+            removed: Vec::new(),
+            inserted: vec![self.create_piece(
+                Buffer::Added,
+                self.added.len() - to_insert.len(),
+                to_insert.len(),
+            )],
+        };
+        let coalesce = matches!(self.last_insert, Some(ChangeRecord { offset: last_offset, .. }) if last_offset == offset);
+        self.history.record(change, coalesce);
+
         match self.last_insert {
             Some(ChangeRecord { offset: last_offset, piece_index }) if last_offset == offset => {
-                self.pieces[piece_index] = self.pieces[piece_index].extend(to_insert);
+                let extended = self.piece(piece_index).extend(to_insert);
+                self.pieces.replace(piece_index, extended);
                 self.last_insert = Some(ChangeRecord {
                     offset: offset + to_insert.len(),
                     piece_index,
@@ -211,129 +212,154 @@ impl TextBuffer for PieceTable {
         self.iter().collect()
     }
 
+    fn chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        let end = std::cmp::min(range.end, self.pieces.length());
+        let location = match range.start < end {
+            true => self.pieces.find_by_offset(range.start),
+            false => None,
+        };
+
+        let chunks = match location {
+            Some(location) => PieceTableChunks {
+                inner: self,
+                pieces: self.pieces.iter_at(location.index),
+                first_piece_offset: range.start - location.start,
+                remaining: end - range.start,
+            },
+            None => PieceTableChunks {
+                inner: self,
+                pieces: self.pieces.iter_at(self.pieces.len()),
+                first_piece_offset: 0,
+                remaining: 0,
+            },
+        };
+
+        Box::new(chunks)
+    }
+
     fn line_at(&self, idx: usize) -> Line {
-        let mut line_start_index = 0;
-        let mut line_end_index = None;
-        let mut item_count = 0;
-        let mut line_start_piece_index = 0;
-
-        if idx == 0 {
-            // Line starts at index 0, ends at first line break found
-            for piece in self.pieces.iter() {
-                if !piece.line_break_offsets.is_empty() {
-                    line_end_index = Some(item_count + piece.line_break_offsets[0]);
-                    break;
+        let line_start_index = match idx {
+            0 => 0,
+            _ => match self.pieces.find_by_line_break(idx) {
+                // Line starts after the idx-th line break
+                Some(location) => {
+                    let piece = self.piece(location.index);
+                    location.start + piece.line_break_offsets[idx - location.line_breaks - 1] + 1
                 }
-                item_count += piece.length;
-            }
-        } else {
-            // Find start index
-            let mut line_breaks_remaining = idx;
-            for (piece_index, piece) in self.pieces.iter().enumerate() {
-                if line_breaks_remaining <= piece.line_break_offsets.len() {
-                    // Line starts in this piece
-                    let line_break_offset = piece.line_break_offsets[line_breaks_remaining - 1];
-                    line_start_index = item_count + line_break_offset + 1;
-                    line_start_piece_index = piece_index;
-
-                    if line_breaks_remaining < piece.line_break_offsets.len() {
-                        // Start of next line is also in this piece
-                        let next_line_break_offset =
-                            piece.line_break_offsets[line_breaks_remaining];
-                        line_end_index = Some(item_count + next_line_break_offset);
-                    }
-                    item_count += piece.length;
-                    break;
-                }
-                line_breaks_remaining = line_breaks_remaining
-                    .checked_sub(piece.line_break_offsets.len())
-                    .unwrap_or(0);
-                item_count += piece.length;
-            }
+                None => self.length,
+            },
+        };
 
-            if line_end_index.is_none() {
-                // Find end index by searching for first line break from line_start_index onwards
-                for piece in self.pieces.iter().skip(line_start_piece_index + 1) {
-                    if !piece.line_break_offsets.is_empty() {
-                        line_end_index = Some(item_count + piece.line_break_offsets[0]);
-                        break;
-                    }
-                    item_count += piece.length;
-                }
+        // Line ends at the following line break, or the end of the buffer
+        let line_end_index = match self.pieces.find_by_line_break(idx + 1) {
+            Some(location) => {
+                let piece = self.piece(location.index);
+                location.start + piece.line_break_offsets[idx - location.line_breaks]
             }
-        }
+            None => self.length,
+        };
 
         let content = self
-            .iter_range(line_start_index..line_end_index.unwrap_or(self.length))
+            .iter_range(line_start_index..line_end_index)
             .collect::<String>();
 
         Line::new(line_start_index, content)
     }
 
+    fn line_index_at(&self, offset: usize) -> usize {
+        match self.pieces.find_by_offset(offset) {
+            Some(location) => {
+                let piece = self.piece(location.index);
+                location.line_breaks + piece
+                    .line_break_offsets
+                    .iter()
+                    .take_while(|x| location.start + **x < offset)
+                    .count()
+            }
+            None => self.pieces.line_breaks(),
+        }
+    }
+
     fn line_count(&self) -> usize {
-        self.pieces
-            .iter()
-            .fold(1, |count, piece| piece.line_break_offsets.len() + count)
+        self.pieces.line_breaks() + 1
     }
 
     fn remove(&mut self, range: Range<usize>) {
-        if range.start >= range.end {
+        let end = std::cmp::min(range.end, self.length);
+        if range.start >= end {
             return;
         }
 
-        self.last_insert = None;
-        let removed_len = range.len();
-
-        for offset in range.rev() {
-            match self.last_remove {
-                Some(ChangeRecord { offset: last_offset, piece_index }) if offset == last_offset => {
-                    if self.pieces[piece_index].length <= 1 {
-                        self.pieces.remove(piece_index);
-                        self.last_remove = piece_index.checked_sub(1).map(|i| {
-                            ChangeRecord {
-                                offset: offset.checked_sub(1).unwrap_or(0),
-                                piece_index: i,
-                            }
-                        });
-                    } else {
-                        self.pieces[piece_index] = self.pieces[piece_index].truncate_right(1);
-                        self.last_remove = Some(ChangeRecord {
-                            offset: offset.checked_sub(1).unwrap_or(0),
-                            piece_index,
-                        });
-                    }
-                }
-                _ => self.raw_remove(offset)
-            }
+        // Consecutive backspaces end where the last removal started; consecutive forward deletes
+        // start there.
+        let coalesce = matches!(self.last_remove, Some(last_offset) if last_offset == end || last_offset == range.start);
+        let removed = self.splice(range.start, end - range.start, Vec::new());
+        self.last_remove = Some(range.start);
+
+        let change = Change {
+            offset: range.start,
+            removed,
+            inserted: Vec::new(),
+        };
+        self.history.record(change, coalesce);
+    }
+
+    fn begin_revision(&mut self) {
+        self.history.begin_revision();
+    }
+
+    fn end_revision(&mut self) {
+        self.history.end_revision();
+    }
+
+    fn undo(&mut self) -> Option<usize> {
+        let revision = self.history.undo()?;
+        for change in revision.iter().rev() {
+            self.splice(change.offset, change.inserted_len(), change.removed.clone());
+        }
 
+        revision.first().map(|c| c.offset + c.removed_len())
+    }
+
+    fn redo(&mut self) -> Option<usize> {
+        let revision = self.history.redo()?;
+        for change in revision.iter() {
+            self.splice(change.offset, change.removed_len(), change.inserted.clone());
         }
 
-        self.length = self.length.checked_sub(removed_len).unwrap_or(0);
+        revision.last().map(|c| c.offset + c.inserted_len())
+    }
+
+    fn mark_saved(&mut self) {
+        self.history.mark_saved();
+    }
+
+    fn is_modified(&self) -> bool {
+        !self.history.is_saved()
     }
 }
 
 struct PieceTableIter<'a> {
     inner: &'a PieceTable,
-    current_piece_index: usize,
+    pieces: piece_tree::Iter<'a>,
+    current_piece: Option<&'a Piece>,
     current_piece_offset: usize,
-    end_piece_index: usize,
-    end_piece_offset: usize,
+    /// Bytes remaining until the end of the iterated range.
+    remaining: usize,
 }
 
 impl<'a> Iterator for PieceTableIter<'a> {
     type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current_piece_index == self.end_piece_index
-            && self.current_piece_offset >= self.end_piece_offset
-        {
+        if self.remaining == 0 {
             return None;
         }
 
-        match self.inner.pieces.get(self.current_piece_index) {
+        match self.current_piece {
             Some(current_piece) => {
                 if self.current_piece_offset >= current_piece.length {
-                    self.current_piece_index += 1;
+                    self.current_piece = self.pieces.next();
                     self.current_piece_offset = 0;
                     return self.next();
                 }
@@ -349,6 +375,7 @@ impl<'a> Iterator for PieceTableIter<'a> {
 
                 if let Some(character) = character {
                     self.current_piece_offset += character.len_utf8();
+                    self.remaining = self.remaining.saturating_sub(character.len_utf8());
                 };
 
                 character
@@ -358,6 +385,38 @@ impl<'a> Iterator for PieceTableIter<'a> {
     }
 }
 
+struct PieceTableChunks<'a> {
+    inner: &'a PieceTable,
+    pieces: piece_tree::Iter<'a>,
+    /// Offset within the first piece at which the first chunk starts.
+    first_piece_offset: usize,
+    /// Bytes remaining until the end of the iterated range.
+    remaining: usize,
+}
+
+impl<'a> Iterator for PieceTableChunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let piece = self.pieces.next()?;
+        let buffer = match piece.buffer {
+            Buffer::Original => &self.inner.original,
+            Buffer::Added => &self.inner.added
+        };
+
+        let start = piece.start + self.first_piece_offset;
+        let length = std::cmp::min(piece.length - self.first_piece_offset, self.remaining);
+        self.first_piece_offset = 0;
+        self.remaining -= length;
+
+        Some(&buffer[start..start + length])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -395,6 +454,11 @@ mod tests {
 
         pt.insert("3", 4);
         assert_eq!(pt.iter().collect::<String>(), "ab0132cd");
+
+        // Insertion into the body of a piece not at the start of the buffer
+        pt.insert("4", 7);
+        pt.insert("5", 2);
+        assert_eq!(pt.iter().collect::<String>(), "ab50132c4d");
     }
 
     #[test]
@@ -436,7 +500,7 @@ mod tests {
                 length: 1,
                 line_break_offsets: Vec::new(),
             },
-        ];
+        ].into();
 
         assert_eq!(pt.iter().collect::<String>(), "ab012cd3");
     }
@@ -470,7 +534,7 @@ mod tests {
                 length: 1,
                 line_break_offsets: Vec::new(),
             },
-        ];
+        ].into();
 
         // ab012cd3
         assert_eq!(pt.iter_range(1..4).collect::<String>(), "b01");
@@ -478,6 +542,19 @@ mod tests {
         assert_eq!(pt.iter_range(4..23).collect::<String>(), "2cd3");
     }
 
+    #[test]
+    fn chunks() {
+        let pt = &mut PieceTable::new(String::from("abcd"));
+        pt.insert("012", 2);
+        pt.insert("3", 7);
+
+        // ab012cd3
+        assert_eq!(vec!["ab", "012", "cd", "3"], pt.chunks(0..8).collect::<Vec<&str>>());
+        assert_eq!(vec!["b", "012", "c"], pt.chunks(1..6).collect::<Vec<&str>>());
+        assert_eq!(vec!["cd", "3"], pt.chunks(5..23).collect::<Vec<&str>>());
+        assert_eq!(0, pt.chunks(8..8).count());
+    }
+
     #[test]
     fn line_at() {
         let pt = &mut PieceTable::new(String::from("ab"));
@@ -553,7 +630,7 @@ mod tests {
                 length: 1,
                 line_break_offsets: Vec::new(),
             },
-        ];
+        ].into();
         pt.length = pt.added.len() + pt.original.len();
 
         pt.remove(0..1);
@@ -592,7 +669,7 @@ mod tests {
                 length: 1,
                 line_break_offsets: Vec::new(),
             },
-        ];
+        ].into();
         pt.length = pt.added.len() + pt.original.len();
 
         pt.remove(3..4);
@@ -631,7 +708,7 @@ mod tests {
                 length: 1,
                 line_break_offsets: Vec::new(),
             },
-        ];
+        ].into();
         pt.length = pt.added.len() + pt.original.len();
 
         pt.remove(1..2);
@@ -665,10 +742,147 @@ mod tests {
                 length: 1,
                 line_break_offsets: Vec::new(),
             },
-        ];
+        ].into();
         pt.length = pt.added.len() + pt.original.len();
 
         pt.remove(7..8);
         assert_eq!(pt.line_at(0).content, "ab012cd3");
     }
+
+    #[test]
+    fn remove_spanning_pieces() {
+        let pt = &mut PieceTable::new(String::from("abcd"));
+        pt.insert("01", 1);
+        pt.insert("2\n3", 5);
+        pt.insert("45", 9);
+        assert_eq!(pt.iter().collect::<String>(), "a01bc2\n3d45");
+        assert_eq!(6, pt.pieces.len());
+
+        pt.remove(2..9);
+        assert_eq!(pt.iter().collect::<String>(), "a045");
+        assert_eq!(3, pt.pieces.len());
+        assert_eq!(4, pt.length);
+        assert_eq!(1, pt.line_count());
+
+        pt.remove(0..4);
+        assert_eq!(pt.iter().collect::<String>(), "");
+        assert!(pt.pieces.is_empty());
+    }
+
+    #[test]
+    fn line_index_at() {
+        let pt = &mut PieceTable::new(String::from("ab\ncd"));
+        pt.insert("\nef", 5);
+
+        assert_eq!(0, pt.line_index_at(0));
+        assert_eq!(0, pt.line_index_at(2));
+        assert_eq!(1, pt.line_index_at(3));
+        assert_eq!(1, pt.line_index_at(5));
+        assert_eq!(2, pt.line_index_at(6));
+        assert_eq!(2, pt.line_index_at(8));
+    }
+
+    #[test]
+    fn undo_redo_insert() {
+        let pt = &mut PieceTable::new(String::from("abcd"));
+        pt.insert("0", 2);
+        pt.insert("1", 3);
+        pt.insert("\n", 0);
+        assert_eq!(pt.iter().collect::<String>(), "\nab01cd");
+
+        assert_eq!(Some(0), pt.undo());
+        assert_eq!(pt.iter().collect::<String>(), "ab01cd");
+
+        // Consecutive typing is undone as a single revision
+        assert_eq!(Some(2), pt.undo());
+        assert_eq!(pt.iter().collect::<String>(), "abcd");
+        assert_eq!(None, pt.undo());
+
+        assert_eq!(Some(4), pt.redo());
+        assert_eq!(pt.iter().collect::<String>(), "ab01cd");
+        assert_eq!(Some(1), pt.redo());
+        assert_eq!(pt.iter().collect::<String>(), "\nab01cd");
+        assert_eq!(None, pt.redo());
+        assert_eq!(2, pt.line_count());
+    }
+
+    #[test]
+    fn undo_redo_remove() {
+        let pt = &mut PieceTable::new(String::from("ab\ncd"));
+        pt.insert("01", 5);
+        pt.remove(6..7);
+        pt.remove(5..6);
+        pt.remove(4..5);
+        assert_eq!(pt.iter().collect::<String>(), "ab\nc");
+
+        // Consecutive backspaces are undone as a single revision
+        assert_eq!(Some(7), pt.undo());
+        assert_eq!(pt.iter().collect::<String>(), "ab\ncd01");
+
+        assert_eq!(Some(4), pt.redo());
+        assert_eq!(pt.iter().collect::<String>(), "ab\nc");
+
+        pt.remove(1..4);
+        assert_eq!(pt.iter().collect::<String>(), "a");
+        assert_eq!(1, pt.line_count());
+        assert_eq!(Some(4), pt.undo());
+        assert_eq!(pt.iter().collect::<String>(), "ab\nc");
+        assert_eq!(2, pt.line_count());
+        assert_eq!("c", pt.line_at(1).content);
+    }
+
+    #[test]
+    fn modified_until_undone_to_save_point() {
+        let pt = &mut PieceTable::new(String::from("abcd"));
+        assert!(!pt.is_modified());
+        pt.insert("0", 4);
+        assert!(pt.is_modified());
+        pt.undo();
+        assert!(!pt.is_modified());
+        pt.redo();
+
+        // Typing straight after saving is not merged into the saved revision
+        pt.mark_saved();
+        pt.insert("1", 5);
+        assert!(pt.is_modified());
+        pt.undo();
+        assert!(!pt.is_modified());
+        assert_eq!(pt.iter().collect::<String>(), "abcd0");
+
+        // The save point cannot be reached again once the history branches before it
+        pt.undo();
+        pt.insert("2", 4);
+        assert!(pt.is_modified());
+        pt.undo();
+        assert!(pt.is_modified());
+    }
+
+    #[test]
+    fn undo_revision() {
+        let pt = &mut PieceTable::new(String::from("abcd"));
+        pt.begin_revision();
+        pt.remove(1..3);
+        pt.insert("012", 1);
+        pt.end_revision();
+        pt.insert("3", 0);
+        assert_eq!(pt.iter().collect::<String>(), "3a012d");
+
+        assert_eq!(Some(0), pt.undo());
+        assert_eq!(pt.iter().collect::<String>(), "a012d");
+        assert_eq!(Some(3), pt.undo());
+        assert_eq!(pt.iter().collect::<String>(), "abcd");
+        assert_eq!(Some(4), pt.redo());
+        assert_eq!(pt.iter().collect::<String>(), "a012d");
+    }
+
+    #[test]
+    fn edit_after_undo_discards_redo() {
+        let pt = &mut PieceTable::new(String::from("abcd"));
+        pt.insert("0", 4);
+        pt.undo();
+        pt.insert("1", 0);
+
+        assert_eq!(None, pt.redo());
+        assert_eq!(pt.iter().collect::<String>(), "1abcd");
+    }
 }
@@ -0,0 +1,542 @@
+use crate::text_buffer::piece::Piece;
+use std::ops::Range;
+
+type Link = Option<Box<Node>>;
+
+struct Node {
+    piece: Piece,
+    left: Link,
+    right: Link,
+    height: usize,
+    /// Number of pieces in this subtree.
+    count: usize,
+    /// Length (in bytes) of all pieces in this subtree.
+    length: usize,
+    /// Number of line breaks within all pieces in this subtree.
+    line_breaks: usize,
+}
+
+impl Node {
+    fn new(piece: Piece) -> Box<Node> {
+        let mut node = Box::new(Node {
+            piece,
+            left: None,
+            right: None,
+            height: 1,
+            count: 1,
+            length: 0,
+            line_breaks: 0,
+        });
+        node.update();
+
+        node
+    }
+
+    fn update(&mut self) {
+        self.height = 1 + std::cmp::max(height(&self.left), height(&self.right));
+        self.count = 1 + count(&self.left) + count(&self.right);
+        self.length = self.piece.length + length(&self.left) + length(&self.right);
+        self.line_breaks = self.piece.line_break_offsets.len()
+            + line_breaks(&self.left)
+            + line_breaks(&self.right);
+    }
+}
+
+fn height(link: &Link) -> usize {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+fn count(link: &Link) -> usize {
+    link.as_ref().map_or(0, |n| n.count)
+}
+
+fn length(link: &Link) -> usize {
+    link.as_ref().map_or(0, |n| n.length)
+}
+
+fn line_breaks(link: &Link) -> usize {
+    link.as_ref().map_or(0, |n| n.line_breaks)
+}
+
+fn rotate_left(mut node: Box<Node>) -> Box<Node> {
+    let mut right = node.right.take().expect("Rotation requires right child");
+    node.right = right.left.take();
+    node.update();
+    right.left = Some(node);
+    right.update();
+
+    right
+}
+
+fn rotate_right(mut node: Box<Node>) -> Box<Node> {
+    let mut left = node.left.take().expect("Rotation requires left child");
+    node.left = left.right.take();
+    node.update();
+    left.right = Some(node);
+    left.update();
+
+    left
+}
+
+fn balance(mut node: Box<Node>) -> Box<Node> {
+    node.update();
+    let left_height = height(&node.left);
+    let right_height = height(&node.right);
+
+    if left_height > right_height + 1 {
+        let left = node.left.take().unwrap();
+        node.left = match height(&left.left) < height(&left.right) {
+            true => Some(rotate_left(left)),
+            false => Some(left),
+        };
+        return rotate_right(node);
+    }
+
+    if right_height > left_height + 1 {
+        let right = node.right.take().unwrap();
+        node.right = match height(&right.right) < height(&right.left) {
+            true => Some(rotate_right(right)),
+            false => Some(right),
+        };
+        return rotate_left(node);
+    }
+
+    node
+}
+
+/// Joins `left`, `middle` and `right` (in that order) into a single balanced tree.
+/// `middle` must not have children.
+fn join(left: Link, mut middle: Box<Node>, right: Link) -> Box<Node> {
+    let left_height = height(&left);
+    let right_height = height(&right);
+
+    if left_height > right_height + 1 {
+        let mut left = left.unwrap();
+        left.right = Some(join(left.right.take(), middle, right));
+        balance(left)
+    } else if right_height > left_height + 1 {
+        let mut right = right.unwrap();
+        right.left = Some(join(left, middle, right.left.take()));
+        balance(right)
+    } else {
+        middle.left = left;
+        middle.right = right;
+        middle.update();
+        middle
+    }
+}
+
+fn pop_last(mut node: Box<Node>) -> (Link, Box<Node>) {
+    match node.right.take() {
+        Some(right) => {
+            let (rest, last) = pop_last(right);
+            node.right = rest;
+            (Some(balance(node)), last)
+        }
+        None => {
+            let rest = node.left.take();
+            node.update();
+            (rest, node)
+        }
+    }
+}
+
+fn concat(left: Link, right: Link) -> Link {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(left), right) => {
+            let (rest, last) = pop_last(left);
+            Some(join(rest, last, right))
+        }
+    }
+}
+
+/// Splits a tree into its first `index` pieces and the remainder.
+fn split(link: Link, index: usize) -> (Link, Link) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            let left = node.left.take();
+            let right = node.right.take();
+            let left_count = count(&left);
+
+            if index <= left_count {
+                let (left_left, left_right) = split(left, index);
+                (left_left, Some(join(left_right, node, right)))
+            } else {
+                let (right_left, right_right) = split(right, index - left_count - 1);
+                (Some(join(left, node, right_left)), right_right)
+            }
+        }
+    }
+}
+
+fn build(pieces: &mut impl Iterator<Item = Piece>, count: usize) -> Link {
+    if count == 0 {
+        return None;
+    }
+
+    let left = build(pieces, count / 2);
+    let mut node = Node::new(pieces.next().expect("Fewer pieces than count"));
+    node.left = left;
+    node.right = build(pieces, count - count / 2 - 1);
+    node.update();
+
+    Some(node)
+}
+
+fn drain_into(link: Link, pieces: &mut Vec<Piece>) {
+    if let Some(node) = link {
+        let node = *node;
+        drain_into(node.left, pieces);
+        pieces.push(node.piece);
+        drain_into(node.right, pieces);
+    }
+}
+
+/// Location of a piece within the tree.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PieceLocation {
+    /// Index of the piece in document order.
+    pub index: usize,
+    /// Byte offset of the start of the piece within the document.
+    pub start: usize,
+    /// Number of line breaks in the document before the start of the piece.
+    pub line_breaks: usize,
+}
+
+/// Balanced (AVL) sequence of pieces, where each node caches the piece count, byte length and
+/// line break count of its subtree so that lookups by index, offset and line are O(log n).
+pub struct PieceTree {
+    root: Link,
+}
+
+impl PieceTree {
+    pub fn new() -> PieceTree {
+        PieceTree { root: None }
+    }
+
+    /// Number of pieces in the tree.
+    pub fn len(&self) -> usize {
+        count(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Total length (in bytes) of all pieces.
+    pub fn length(&self) -> usize {
+        length(&self.root)
+    }
+
+    /// Total number of line breaks within all pieces.
+    pub fn line_breaks(&self) -> usize {
+        line_breaks(&self.root)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Piece> {
+        let mut link = &self.root;
+        let mut index = index;
+        while let Some(node) = link {
+            let left_count = count(&node.left);
+            if index < left_count {
+                link = &node.left;
+            } else if index == left_count {
+                return Some(&node.piece);
+            } else {
+                index -= left_count + 1;
+                link = &node.right;
+            }
+        }
+
+        None
+    }
+
+    pub fn replace(&mut self, index: usize, piece: Piece) {
+        fn replace_in(node: &mut Box<Node>, index: usize, piece: Piece) {
+            let left_count = count(&node.left);
+            if index < left_count {
+                replace_in(node.left.as_mut().unwrap(), index, piece);
+            } else if index == left_count {
+                node.piece = piece;
+            } else {
+                replace_in(node.right.as_mut().unwrap(), index - left_count - 1, piece);
+            }
+            node.update();
+        }
+
+        assert!(index < self.len(), "Piece index out of range");
+        replace_in(self.root.as_mut().unwrap(), index, piece);
+    }
+
+    pub fn insert(&mut self, index: usize, piece: Piece) {
+        let (left, right) = split(self.root.take(), index);
+        self.root = Some(join(left, Node::new(piece), right));
+    }
+
+    pub fn push(&mut self, piece: Piece) {
+        self.insert(self.len(), piece);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Piece {
+        let (left, right) = split(self.root.take(), index);
+        let (middle, right) = split(right, 1);
+        self.root = concat(left, right);
+
+        let middle = middle.expect("Piece index out of range");
+        middle.piece
+    }
+
+    /// Replaces the pieces at indices `range` with `pieces`, returning the removed pieces.
+    pub fn splice(&mut self, range: Range<usize>, pieces: Vec<Piece>) -> Vec<Piece> {
+        let (left, rest) = split(self.root.take(), range.start);
+        let (middle, right) = split(rest, range.end - range.start);
+
+        let mut removed = Vec::new();
+        drain_into(middle, &mut removed);
+
+        let inserted_count = pieces.len();
+        let inserted = build(&mut pieces.into_iter(), inserted_count);
+        self.root = concat(concat(left, inserted), right);
+
+        removed
+    }
+
+    /// Locates the piece containing byte `offset`, or `None` if the offset is beyond the end.
+    pub fn find_by_offset(&self, offset: usize) -> Option<PieceLocation> {
+        let mut link = &self.root;
+        let mut location = PieceLocation {
+            index: 0,
+            start: 0,
+            line_breaks: 0,
+        };
+
+        while let Some(node) = link {
+            let left_length = length(&node.left);
+            if offset < location.start + left_length {
+                link = &node.left;
+                continue;
+            }
+
+            location.index += count(&node.left);
+            location.start += left_length;
+            location.line_breaks += line_breaks(&node.left);
+            if offset < location.start + node.piece.length {
+                return Some(location);
+            }
+
+            location.index += 1;
+            location.start += node.piece.length;
+            location.line_breaks += node.piece.line_break_offsets.len();
+            link = &node.right;
+        }
+
+        None
+    }
+
+    /// Locates the piece containing the `n`th (1-based) line break, or `None` if there are fewer.
+    pub fn find_by_line_break(&self, n: usize) -> Option<PieceLocation> {
+        let mut link = &self.root;
+        let mut location = PieceLocation {
+            index: 0,
+            start: 0,
+            line_breaks: 0,
+        };
+
+        while let Some(node) = link {
+            let left_line_breaks = line_breaks(&node.left);
+            if n <= location.line_breaks + left_line_breaks {
+                link = &node.left;
+                continue;
+            }
+
+            location.index += count(&node.left);
+            location.start += length(&node.left);
+            location.line_breaks += left_line_breaks;
+            if n <= location.line_breaks + node.piece.line_break_offsets.len() {
+                return Some(location);
+            }
+
+            location.index += 1;
+            location.start += node.piece.length;
+            location.line_breaks += node.piece.line_break_offsets.len();
+            link = &node.right;
+        }
+
+        None
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.iter_at(0)
+    }
+
+    /// In-order iterator over pieces, starting from the piece at `index`.
+    pub fn iter_at(&self, index: usize) -> Iter<'_> {
+        let mut stack = Vec::new();
+        let mut link = &self.root;
+        let mut index = index;
+        while let Some(node) = link {
+            let left_count = count(&node.left);
+            if index < left_count {
+                stack.push(node.as_ref());
+                link = &node.left;
+            } else if index == left_count {
+                stack.push(node.as_ref());
+                break;
+            } else {
+                index -= left_count + 1;
+                link = &node.right;
+            }
+        }
+
+        Iter { stack }
+    }
+}
+
+impl Default for PieceTree {
+    fn default() -> PieceTree {
+        PieceTree::new()
+    }
+}
+
+impl From<Vec<Piece>> for PieceTree {
+    fn from(pieces: Vec<Piece>) -> Self {
+        let piece_count = pieces.len();
+        PieceTree {
+            root: build(&mut pieces.into_iter(), piece_count),
+        }
+    }
+}
+
+pub struct Iter<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Piece;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let mut link = &node.right;
+        while let Some(child) = link {
+            self.stack.push(child.as_ref());
+            link = &child.left;
+        }
+
+        Some(&node.piece)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_buffer::piece::Buffer;
+
+    fn piece(start: usize, length: usize, line_break_offsets: Vec<usize>) -> Piece {
+        Piece {
+            buffer: Buffer::Added,
+            start,
+            length,
+            line_break_offsets,
+        }
+    }
+
+    fn assert_balanced(link: &Link) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                let left_height = assert_balanced(&node.left);
+                let right_height = assert_balanced(&node.right);
+                assert!((left_height as isize - right_height as isize).abs() <= 1);
+                assert_eq!(node.height, 1 + std::cmp::max(left_height, right_height));
+                node.height
+            }
+        }
+    }
+
+    fn starts(tree: &PieceTree) -> Vec<usize> {
+        tree.iter().map(|p| p.start).collect()
+    }
+
+    #[test]
+    fn insert_remove_stays_balanced() {
+        let tree = &mut PieceTree::new();
+        for i in 0..100 {
+            tree.push(piece(i, 1, Vec::new()));
+        }
+        for i in 0..100 {
+            tree.insert(i * 2, piece(100 + i, 1, Vec::new()));
+        }
+        assert_balanced(&tree.root);
+        assert_eq!(200, tree.len());
+        assert_eq!(Some(100), tree.get(0).map(|p| p.start));
+        assert_eq!(Some(0), tree.get(1).map(|p| p.start));
+
+        for _ in 0..100 {
+            tree.remove(0);
+        }
+        assert_balanced(&tree.root);
+        assert_eq!(100, tree.len());
+        assert_eq!(100, tree.length());
+    }
+
+    #[test]
+    fn splice() {
+        let tree = &mut PieceTree::from((0..10).map(|i| piece(i, 1, Vec::new())).collect::<Vec<Piece>>());
+
+        let removed = tree.splice(2..5, vec![piece(20, 1, Vec::new()), piece(21, 1, Vec::new())]);
+        assert_eq!(vec![2, 3, 4], removed.iter().map(|p| p.start).collect::<Vec<usize>>());
+        assert_eq!(vec![0, 1, 20, 21, 5, 6, 7, 8, 9], starts(tree));
+        assert_balanced(&tree.root);
+
+        tree.splice(0..9, Vec::new());
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn find_by_offset() {
+        let tree = PieceTree::from(vec![
+            piece(0, 3, vec![1]),
+            piece(3, 2, Vec::new()),
+            piece(5, 4, vec![0, 3]),
+        ]);
+
+        let expected = PieceLocation {
+            index: 2,
+            start: 5,
+            line_breaks: 1,
+        };
+        assert_eq!(Some(expected), tree.find_by_offset(6));
+        assert_eq!(Some(1), tree.find_by_offset(3).map(|l| l.index));
+        assert_eq!(None, tree.find_by_offset(9));
+    }
+
+    #[test]
+    fn find_by_line_break() {
+        let tree = PieceTree::from(vec![
+            piece(0, 3, vec![1]),
+            piece(3, 2, Vec::new()),
+            piece(5, 4, vec![0, 3]),
+        ]);
+
+        assert_eq!(Some(0), tree.find_by_line_break(1).map(|l| l.index));
+        let expected = PieceLocation {
+            index: 2,
+            start: 5,
+            line_breaks: 1,
+        };
+        assert_eq!(Some(expected), tree.find_by_line_break(3));
+        assert_eq!(None, tree.find_by_line_break(4));
+        assert_eq!(3, tree.line_breaks());
+    }
+
+    #[test]
+    fn iter_at() {
+        let tree = PieceTree::from((0..10).map(|i| piece(i, 1, Vec::new())).collect::<Vec<Piece>>());
+
+        assert_eq!(vec![7, 8, 9], tree.iter_at(7).map(|p| p.start).collect::<Vec<usize>>());
+        assert_eq!(0, tree.iter_at(10).count());
+    }
+}
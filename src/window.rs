@@ -3,6 +3,14 @@ pub struct Window {
     pub width: u16,
     pub vertical_offset: usize,
     pub horizontal_offset: usize,
+    /// Columns to the left of the window taken up by line numbers.
+    pub gutter_width: u16,
+    /// Rows of the line at `vertical_offset` scrolled out of view, when it is soft wrapped onto
+    /// more rows than fit in the window.
+    pub skipped_rows: usize,
+    /// Whether the window scrolls to keep the cursor in view. Cleared while it is scrolled
+    /// independently of the cursor, such as by the mouse wheel.
+    pub follow_cursor: bool,
 }
 
 impl Window {
@@ -17,6 +25,9 @@ impl Window {
             width,
             vertical_offset: 0,
             horizontal_offset: 0,
+            gutter_width: 0,
+            skipped_rows: 0,
+            follow_cursor: true,
         }
     }
 
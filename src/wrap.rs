@@ -0,0 +1,145 @@
+use crate::grapheme::{Grapheme, Whitespace};
+use std::ops::Range;
+
+/// Whether a row may be broken after `grapheme`.
+fn is_break(grapheme: &Grapheme) -> bool {
+    match grapheme.whitespace {
+        Some(Whitespace::Tab) | Some(Whitespace::TrailingSpace) => true,
+        Some(Whitespace::NonBreakingSpace) => false,
+        None => !grapheme.content.is_empty() && grapheme.content.chars().all(|c| c == ' '),
+    }
+}
+
+/// Number of columns taken up by `graphemes`.
+pub fn width_of(graphemes: &[Grapheme]) -> usize {
+    graphemes.iter().map(|g| g.width()).sum()
+}
+
+/// Splits the graphemes of a line into rows no wider than `width`, breaking after whitespace
+/// where possible. A row is only broken mid-word if the word alone is wider than `width`.
+/// If the last row is full, an empty row follows it to hold the end of the line.
+pub fn wrap(graphemes: &[Grapheme], width: usize) -> Vec<Range<usize>> {
+    let width = std::cmp::max(width, 1);
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut row_width = 0;
+    // Index after the last break opportunity in the current row
+    let mut last_break = None;
+    let mut i = 0;
+    while i < graphemes.len() {
+        let grapheme_width = graphemes[i].width();
+        if row_width + grapheme_width > width && i > start {
+            let end = match last_break {
+                Some(end) => end,
+                None => i,
+            };
+            rows.push(start..end);
+            start = end;
+            row_width = width_of(&graphemes[start..i]);
+            last_break = None;
+            continue;
+        }
+
+        row_width += grapheme_width;
+        if is_break(&graphemes[i]) {
+            last_break = Some(i + 1);
+        }
+        i += 1;
+    }
+    rows.push(start..graphemes.len());
+
+    if row_width >= width {
+        rows.push(graphemes.len()..graphemes.len());
+    }
+    rows
+}
+
+/// Index of the row in `rows` the cursor before grapheme `character` is shown on. A cursor at
+/// the boundary between two rows is shown at the start of the latter.
+pub fn row_of(rows: &[Range<usize>], character: usize) -> usize {
+    rows.iter()
+        .position(|row| row.contains(&character))
+        .unwrap_or(rows.len() - 1)
+}
+
+/// Index of the grapheme on `row` covering `column`, counted from the start of the row. If the
+/// row is narrower, the end of the row, which on all but the last row is its final grapheme.
+pub fn character_at_column(
+    graphemes: &[Grapheme],
+    rows: &[Range<usize>],
+    row: usize,
+    column: usize,
+) -> usize {
+    let range = rows[row].clone();
+    let mut row_width = 0;
+    for i in range.clone() {
+        row_width += graphemes[i].width();
+        if row_width > column {
+            return i;
+        }
+    }
+
+    match row + 1 < rows.len() && !range.is_empty() {
+        true => range.end - 1,
+        false => range.end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EscapePolicy;
+    use crate::text_buffer::line::Line;
+
+    fn graphemes(s: &str) -> Vec<Grapheme> {
+        Grapheme::from_line(&Line::new(0, String::from(s)), 4, EscapePolicy::Native)
+    }
+
+    fn rows_content(s: &str, width: usize) -> Vec<String> {
+        let graphemes = graphemes(s);
+        wrap(&graphemes, width)
+            .into_iter()
+            .map(|row| graphemes[row].iter().map(|g| g.content.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn wrapped_at_word_boundaries() {
+        assert_eq!(vec!["the quick ", "brown fox"], rows_content("the quick brown fox", 10));
+        assert_eq!(vec!["short"], rows_content("short", 10));
+        assert_eq!(vec![""], rows_content("", 10));
+    }
+
+    #[test]
+    fn long_word_broken() {
+        assert_eq!(vec!["a ", "abcde", "fgh"], rows_content("a abcdefgh", 5));
+    }
+
+    #[test]
+    fn wide_graphemes_not_split() {
+        assert_eq!(vec!["中文", "字"], rows_content("中文字", 5));
+    }
+
+    #[test]
+    fn non_breaking_space_not_broken_at() {
+        assert_eq!(vec!["ab\u{a0}c", "d"], rows_content("ab\u{a0}cd", 4));
+    }
+
+    #[test]
+    fn full_last_row_followed_by_empty_row() {
+        assert_eq!(vec!["abc ", "defg", ""], rows_content("abc defg", 4));
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let graphemes = graphemes("the quick brown fox");
+        let rows = wrap(&graphemes, 10);
+
+        assert_eq!(0, row_of(&rows, 9));
+        assert_eq!(1, row_of(&rows, 10));
+        assert_eq!(1, row_of(&rows, 19));
+        assert_eq!(12, character_at_column(&graphemes, &rows, 1, 2));
+        assert_eq!(9, character_at_column(&graphemes, &rows, 0, 15));
+        assert_eq!(19, character_at_column(&graphemes, &rows, 1, 15));
+    }
+}